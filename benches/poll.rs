@@ -0,0 +1,91 @@
+//! Per-poll cost of `SlogScope`, compared to polling the bare future, and
+//! throughput of scoped streams.
+//!
+//! Run with `cargo bench`. `cargo test` also runs each benchmark once, to
+//! make sure they keep working.
+
+use std::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use futures_util::{
+    future,
+    stream::{self, StreamExt as _},
+    task::noop_waker_ref,
+};
+use slog::o;
+use slog_scope_futures::{futures03::StreamExt, FutureExt};
+
+fn pending() -> impl Future<Output = ()> {
+    future::poll_fn(|_| {
+        black_box(());
+        Poll::Pending
+    })
+}
+
+fn poll_repeatedly(c: &mut Criterion, name: &str, fut: impl Future<Output = ()>) {
+    let mut fut = pin!(fut);
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    c.bench_function(name, |b| b.iter(|| fut.as_mut().poll(&mut cx)));
+}
+
+fn poll(c: &mut Criterion) {
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+
+    poll_repeatedly(c, "poll/bare", pending());
+    poll_repeatedly(c, "poll/scoped", pending().with_logger(&logger));
+}
+
+fn nesting(c: &mut Criterion) {
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+
+    let shared = pending()
+        .with_logger(&logger)
+        .with_logger(&logger)
+        .with_logger(&logger);
+    poll_repeatedly(c, "nesting/3 layers, shared logger", shared);
+
+    let cloned = pending()
+        .with_logger(logger.clone())
+        .with_logger(logger.clone())
+        .with_logger(logger.clone());
+    poll_repeatedly(c, "nesting/3 layers, cloned loggers", cloned);
+
+    let distinct = pending()
+        .with_logger(logger.new(o!("layer" => "client")))
+        .with_logger(logger.new(o!("layer" => "service")))
+        .with_logger(&logger);
+    poll_repeatedly(c, "nesting/3 layers, distinct loggers", distinct);
+}
+
+fn streams(c: &mut Criterion) {
+    const ITEMS: u64 = 10_000;
+
+    let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    let items = || stream::iter(0..ITEMS).map(|i| black_box(i * 2));
+
+    let mut group = c.benchmark_group("stream");
+    group.throughput(Throughput::Elements(ITEMS));
+    group.bench_function("bare", |b| {
+        b.iter_batched(
+            items,
+            |s| futures_executor::block_on(s.count()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("scoped", |b| {
+        b.iter_batched(
+            || items().with_logger(&logger),
+            |s| futures_executor::block_on(s.count()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, poll, nesting, streams);
+criterion_main!(benches);
@@ -0,0 +1,27 @@
+//! Sets `cfg(nightly)` when the `nightly-async-iter` feature is enabled on a
+//! nightly compiler, so `--all-features` still builds on stable.
+
+use std::{env, process::Command};
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(nightly)");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+
+    if env::var_os("CARGO_FEATURE_NIGHTLY_ASYNC_ITER").is_none() {
+        return;
+    }
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let nightly = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .map(|out| {
+            let version = String::from_utf8_lossy(&out.stdout);
+            version.contains("-nightly") || version.contains("-dev")
+        })
+        .unwrap_or(false);
+
+    if nightly {
+        println!("cargo:rustc-cfg=nightly");
+    }
+}
@@ -0,0 +1,36 @@
+//! An axum app logging every request under its own scope.
+//!
+//! Try `curl -H 'x-request-id: abc' localhost:3000/hello/world`.
+
+use axum::{extract::Path, routing::get, Router};
+use slog::{info, o, Drain, Logger};
+use slog_scope_futures::axum::{ScopeLayer, ScopedLogger};
+
+async fn hello(Path(name): Path<String>) -> String {
+    slog_scope::info!("saying hello"; "name" => &name);
+    format!("Hello, {}!\n", name)
+}
+
+async fn whoami(ScopedLogger(logger): ScopedLogger) -> String {
+    info!(logger, "explicitly logged");
+    format!("{:?}\n", logger)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let decorator = slog_term::PlainSyncDecorator::new(std::io::stdout());
+    let drain = slog_term::FullFormat::new(decorator).build().fuse();
+    let root = Logger::root(drain, o!());
+    let _guard = slog_scope::set_global_logger(root.clone());
+
+    let app = Router::new()
+        .route("/hello/{name}", get(hello))
+        .route("/whoami", get(whoami))
+        .layer(ScopeLayer::new(root.clone()));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
+        .await
+        .unwrap();
+    info!(root, "listening"; "addr" => %listener.local_addr().unwrap());
+    axum::serve(listener, app).await.unwrap();
+}
@@ -0,0 +1,37 @@
+//! Gives each component of a tower stack its own `component` KV.
+//!
+//! Everything below the `SlogScopeLayer`, including the timeout middleware
+//! and the futures it drives, runs under the component's logger.
+
+use std::time::Duration;
+
+use slog::{info, o, Drain, Logger};
+use slog_scope_futures::tower::SlogScopeLayer;
+use tower::{service_fn, Service, ServiceBuilder, ServiceExt};
+
+async fn query(id: u32) -> Result<u32, tower::BoxError> {
+    slog_scope::info!("running query"; "id" => id);
+    tokio::time::sleep(Duration::from_millis(10 * u64::from(id))).await;
+    slog_scope::info!("query finished"; "id" => id);
+    Ok(id)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let decorator = slog_term::PlainSyncDecorator::new(std::io::stdout());
+    let drain = slog_term::FullFormat::new(decorator).build().fuse();
+    let root = Logger::root(drain, o!());
+    let _guard = slog_scope::set_global_logger(root.clone());
+
+    let mut db = ServiceBuilder::new()
+        .layer(SlogScopeLayer::new(root.new(o!("component" => "db"))))
+        .timeout(Duration::from_millis(25))
+        .service(service_fn(query));
+
+    for id in 1..=3 {
+        match db.ready().await.unwrap().call(id).await {
+            Ok(id) => info!(root, "query succeeded"; "id" => id),
+            Err(err) => info!(root, "query failed"; "id" => id, "error" => %err),
+        }
+    }
+}
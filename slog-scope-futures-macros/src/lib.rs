@@ -0,0 +1,538 @@
+//! Attribute macros for `slog-scope-futures`.
+//!
+//! These are re-exported by `slog-scope-futures` behind its `macros` feature,
+//! and shouldn't be depended on directly.
+
+#![warn(missing_docs)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    spanned::Spanned,
+    Attribute, Error, Expr, ItemFn, LitInt, LitStr, ReturnType, Token, Type,
+};
+
+/// Run the body of an async function in a slog scope.
+///
+/// The argument is either an `o!` invocation, extending the current scope's
+/// logger with its KVs, or any other expression evaluating to a `Logger` or a
+/// reference to one. It's evaluated before the body runs, i.e. on first poll
+/// for `async fn`s, and when called for functions returning `impl Future`.
+///
+/// ```
+/// use slog::o;
+/// use slog_scope_futures::slog_scope;
+///
+/// struct Fetcher {
+///     logger: slog::Logger,
+/// }
+///
+/// impl Fetcher {
+///     #[slog_scope(self.logger.new(o!("url" => url.to_owned())))]
+///     async fn fetch(&self, url: &str) -> String {
+///         format!("{:?}", slog_scope::logger())
+///     }
+/// }
+///
+/// #[slog_scope(o!("component" => "parser"))]
+/// async fn parse<T: std::str::FromStr>(input: &str) -> Result<(T, String), T::Err> {
+///     let value = input.parse()?;
+///     Ok((value, format!("{:?}", slog_scope::logger())))
+/// }
+///
+/// let fetcher = Fetcher {
+///     logger: slog::Logger::root(slog::Discard, o!("job" => 1)),
+/// };
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// futures_executor::block_on(async {
+///     assert_eq!(fetcher.fetch("/").await, "Logger(url, job)");
+///
+///     let parsed = slog_scope::scope(&request, || parse::<u32>("1"));
+///     assert_eq!(parsed.await, Ok((1, "Logger(component)".to_owned())));
+/// });
+/// ```
+///
+/// Note that in the last case, the current scope is the one the function is
+/// polled in. Functions returning `impl Future` pick up their logger when
+/// called instead:
+///
+/// ```
+/// use std::future::Future;
+/// use slog::o;
+/// use slog_scope_futures::slog_scope;
+///
+/// #[slog_scope(o!("component" => "parser"))]
+/// fn parse(input: &str) -> impl Future<Output = String> + '_ {
+///     async move { format!("{} {:?}", input, slog_scope::logger()) }
+/// }
+///
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+/// let parsed = slog_scope::scope(&request, || parse("1"));
+///
+/// assert_eq!(futures_executor::block_on(parsed), "1 Logger(component, request_id)");
+/// ```
+#[proc_macro_attribute]
+pub fn slog_scope(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let logger = parse_macro_input!(attr as Expr);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand(logger, item).into()
+}
+
+fn expand(logger: Expr, item: ItemFn) -> TokenStream2 {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    let logger = if is_kv_macro(&logger) {
+        quote! { ::slog_scope_futures::__private::current_logger().new(#logger) }
+    } else {
+        quote! { #logger }
+    };
+
+    // Keep the binding from clashing with the function's own arguments.
+    let binding = Ident::new("logger", Span::mixed_site());
+
+    let body = if sig.asyncness.is_some() {
+        quote! {
+            let #binding = #logger;
+            ::slog_scope_futures::SlogScope::new(#binding, async move #block).await
+        }
+    } else if returns_impl_trait(&sig.output) {
+        quote! {
+            let #binding = #logger;
+            ::slog_scope_futures::SlogScope::new(#binding, (|| #block)())
+        }
+    } else {
+        return quote_spanned! {sig.span()=>
+            compile_error!("`slog_scope` expects an `async fn` or a function returning `impl Future`");
+        };
+    };
+
+    quote! {
+        #(#attrs)* #vis #sig {
+            #body
+        }
+    }
+}
+
+/// Set up the global logger and run `async fn main` in its scope.
+///
+/// The argument is an expression evaluating to the root `Logger`, or
+/// `drain = ..` for a drain to build one from. It's installed as the global
+/// logger for the whole of `main`, and the body runs in its scope:
+///
+/// ```
+/// use slog::{o, Drain};
+///
+/// fn root_logger() -> slog::Logger {
+///     slog::Logger::root(slog::Discard, o!("app" => "billing"))
+/// }
+///
+/// #[slog_scope_futures::main(root_logger(), flavor = "current_thread")]
+/// async fn main() {
+///     assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(app)");
+///
+///     let task = tokio::spawn(async { format!("{:?}", slog_scope::logger()) });
+///     assert_eq!(task.await.unwrap(), "Logger(app)");
+/// }
+/// ```
+///
+/// The runtime is built by the attribute, and configured with:
+///
+/// - `runtime = "tokio"` (the default), `runtime = "async-std"` or
+///   `runtime = "futures"`, which need the matching features of
+///   `slog-scope-futures`,
+/// - `flavor = "multi_thread"` (the default) or `flavor = "current_thread"`,
+///   and `worker_threads = N`, for tokio.
+///
+/// Alternatively, put a runtime's own attribute after this one to leave the
+/// runtime to it:
+///
+/// ```
+/// # use slog::o;
+/// #[slog_scope_futures::main(drain = slog::Discard)]
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() -> Result<(), String> {
+///     slog_scope::info!("started");
+///     Ok(())
+/// }
+/// ```
+///
+/// Mistakes in the arguments are reported at compile time:
+///
+/// ```compile_fail
+/// #[slog_scope_futures::main(drain = slog::Discard, runtime = "smol")]
+/// async fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// #[slog_scope_futures::main(drain = slog::Discard)]
+/// fn main() {}
+/// ```
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as Args);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand_main(args, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Run an async test under a logger of its own, capturing what it logs.
+///
+/// The test gets a fresh `CapturingDrain` from `slog_scope_futures::test_util`
+/// as its argument, if it takes one, and its body runs in the scope of a
+/// logger over that drain. The global logger is left alone, so tests can run
+/// in parallel without seeing each other's records:
+///
+/// ```
+/// use slog_scope_futures::test_util::CapturingDrain;
+///
+/// #[slog_scope_futures::test]
+/// async fn logs_the_greeting(records: CapturingDrain) {
+///     slog_scope::info!("hello");
+///     assert!(records.contains_msg("hello"));
+/// }
+/// ```
+///
+/// Tests run on `futures_executor::block_on` unless configured like
+/// [`main`](macro@crate::main), or followed by a runtime's own test attribute:
+///
+/// ```
+/// use slog_scope_futures::test_util::CapturingDrain;
+///
+/// #[slog_scope_futures::test]
+/// #[tokio::test(flavor = "current_thread")]
+/// async fn sleeps(records: CapturingDrain) {
+///     tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+///     slog_scope::info!("woke up");
+///     assert_eq!(records.len(), 1);
+/// }
+/// ```
+///
+/// Only what's logged through the scope is captured: tasks spawned without
+/// carrying it, e.g. by a bare `tokio::spawn`, log to the global logger.
+/// This needs the `test-util` feature of `slog-scope-futures`.
+///
+/// Like `tokio::test`, it shadows the built-in `#[test]` when the crate root
+/// is glob imported, so glob imports are best kept inside test functions.
+///
+/// ```compile_fail
+/// #[slog_scope_futures::test(drain = slog::Discard)]
+/// async fn logs_nowhere() {}
+/// ```
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as Args);
+    let item = parse_macro_input!(item as ItemFn);
+
+    expand_test(args, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Where the root logger of `main` comes from.
+enum Root {
+    Logger(Expr),
+    Drain(Expr),
+}
+
+/// The arguments of `main` and `test`.
+struct Args {
+    root: Option<Root>,
+    runtime: Option<LitStr>,
+    flavor: Option<LitStr>,
+    worker_threads: Option<LitInt>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut args = Args {
+            root: None,
+            runtime: None,
+            flavor: None,
+            worker_threads: None,
+        };
+
+        while !input.is_empty() {
+            let keyed =
+                input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]);
+            let key = if keyed {
+                let key: Ident = input.parse()?;
+                input.parse::<Token![=]>()?;
+                Some(key)
+            } else {
+                None
+            };
+
+            let span = input.span();
+            let duplicate = match key.as_ref().map(|key| key.to_string()).as_deref() {
+                None | Some("logger") => args.root.replace(Root::Logger(input.parse()?)).is_some(),
+                Some("drain") => args.root.replace(Root::Drain(input.parse()?)).is_some(),
+                Some("runtime") => args.runtime.replace(input.parse()?).is_some(),
+                Some("flavor") => args.flavor.replace(input.parse()?).is_some(),
+                Some("worker_threads") => args.worker_threads.replace(input.parse()?).is_some(),
+                Some(other) => {
+                    return Err(Error::new(
+                        key.span(),
+                        format!(
+                            "unknown argument `{}`, expected `logger`, `drain`, `runtime`, `flavor` or `worker_threads`",
+                            other
+                        ),
+                    ))
+                }
+            };
+            if duplicate {
+                return Err(Error::new(span, "argument given more than once"));
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn expand_main(args: Args, item: ItemFn) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = item;
+
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "`slog_scope_futures::main` expects an `async fn`",
+        ));
+    }
+
+    let root = match &args.root {
+        Some(Root::Logger(logger)) => quote! { #logger },
+        Some(Root::Drain(drain)) => {
+            quote! { ::slog_scope_futures::__private::root_logger(#drain) }
+        }
+        None => {
+            return Err(Error::new(
+                Span::call_site(),
+                "expected the root logger, e.g. `#[slog_scope_futures::main(build_logger())]`",
+            ))
+        }
+    };
+
+    let logger = Ident::new("logger", Span::mixed_site());
+    let guard = Ident::new("guard", Span::mixed_site());
+    let setup = quote! {
+        let #logger: ::slog_scope_futures::__private::Logger = #root;
+        let #guard = ::slog_scope_futures::__private::set_global_logger(#logger.clone());
+    };
+    let scoped = quote! { ::slog_scope_futures::SlogScope::new(#logger, async move #block) };
+
+    // With a runtime attribute of its own, `main` is left async for it.
+    if attrs.iter().any(|attr| is_attr(attr, "main")) {
+        args.deny_runtime()?;
+
+        return Ok(quote! {
+            #(#attrs)* #vis #sig {
+                #setup
+                #scoped.await
+            }
+        });
+    }
+
+    let run = args.block_on("tokio", scoped)?;
+    sig.asyncness = None;
+
+    Ok(quote! {
+        #(#attrs)* #vis #sig {
+            #setup
+            #run
+        }
+    })
+}
+
+fn expand_test(args: Args, item: ItemFn) -> syn::Result<TokenStream2> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "`slog_scope_futures::test` expects an `async fn`",
+        ));
+    }
+    if let Some(Root::Logger(expr) | Root::Drain(expr)) = &args.root {
+        return Err(Error::new(
+            expr.span(),
+            "tests log to a `CapturingDrain` of their own",
+        ));
+    }
+    if sig.inputs.len() > 1 {
+        return Err(Error::new(
+            sig.inputs.span(),
+            "expected at most one argument, the test's `CapturingDrain`",
+        ));
+    }
+
+    let ident = &sig.ident;
+    let records = Ident::new("records", Span::mixed_site());
+    let logger = Ident::new("logger", Span::mixed_site());
+    let call = if sig.inputs.is_empty() {
+        quote! { #ident() }
+    } else {
+        quote! { #ident(::core::clone::Clone::clone(&#records)) }
+    };
+    // The test itself becomes a function local to the generated one.
+    let setup = quote! {
+        #sig #block
+
+        let #records = ::slog_scope_futures::test_util::CapturingDrain::new();
+        let #logger = #records.logger();
+    };
+    let scoped = quote! { ::slog_scope_futures::SlogScope::new(#logger, #call) };
+
+    let mut outer = sig.clone();
+    outer.inputs = Default::default();
+
+    // With a runtime attribute of its own, the test is left async for it.
+    if attrs.iter().any(|attr| is_attr(attr, "test")) {
+        args.deny_runtime()?;
+
+        return Ok(quote! {
+            #(#attrs)* #vis #outer {
+                #setup
+                #scoped.await
+            }
+        });
+    }
+
+    let run = args.block_on("futures", scoped)?;
+    outer.asyncness = None;
+
+    Ok(quote! {
+        #[test]
+        #(#attrs)* #vis #outer {
+            #setup
+            #run
+        }
+    })
+}
+
+impl Args {
+    fn runtime_spans(&self) -> impl Iterator<Item = Span> {
+        let spans = [
+            self.runtime.as_ref().map(Spanned::span),
+            self.flavor.as_ref().map(Spanned::span),
+            self.worker_threads.as_ref().map(Spanned::span),
+        ];
+        IntoIterator::into_iter(spans).flatten()
+    }
+
+    /// Fail if a runtime is configured, as another attribute drives it.
+    fn deny_runtime(&self) -> syn::Result<()> {
+        match self.runtime_spans().next() {
+            Some(span) => Err(Error::new(
+                span,
+                "the runtime is configured by the runtime's own attribute",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Run `future` to completion on the configured runtime.
+    fn block_on(&self, default: &str, future: TokenStream2) -> syn::Result<TokenStream2> {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .map_or(default.to_owned(), LitStr::value);
+
+        if runtime == "tokio" {
+            let multi_thread = match self.flavor.as_ref().map(LitStr::value).as_deref() {
+                None | Some("multi_thread") => true,
+                Some("current_thread") => false,
+                Some(_) => {
+                    return Err(Error::new(
+                        self.flavor.span(),
+                        "unknown flavor, expected `multi_thread` or `current_thread`",
+                    ))
+                }
+            };
+            let worker_threads = match &self.worker_threads {
+                Some(n) if !multi_thread => {
+                    return Err(Error::new(
+                        n.span(),
+                        "`worker_threads` only applies to the `multi_thread` flavor",
+                    ))
+                }
+                Some(n) => quote! { ::core::option::Option::Some(#n) },
+                None => quote! { ::core::option::Option::None },
+            };
+
+            return Ok(quote! {
+                ::slog_scope_futures::__private::block_on_tokio(#multi_thread, #worker_threads, #future)
+            });
+        }
+
+        let block_on = match runtime.as_str() {
+            "async-std" => quote! { block_on_async_std },
+            "futures" => quote! { block_on_futures },
+            _ => {
+                return Err(Error::new(
+                    self.runtime.span(),
+                    "unknown runtime, expected `tokio`, `async-std` or `futures`",
+                ))
+            }
+        };
+        let tokio_only = [
+            self.flavor.as_ref().map(Spanned::span),
+            self.worker_threads.as_ref().map(Spanned::span),
+        ];
+        if let Some(span) = IntoIterator::into_iter(tokio_only).flatten().next() {
+            return Err(Error::new(
+                span,
+                format!("{} runtimes aren't configurable", runtime),
+            ));
+        }
+
+        Ok(quote! { ::slog_scope_futures::__private::#block_on(#future) })
+    }
+}
+
+/// Whether `attr` is e.g. `#[tokio::main]` for a `name` of `main`.
+fn is_attr(attr: &Attribute, name: &str) -> bool {
+    attr.path().segments.last().is_some_and(|s| s.ident == name)
+}
+
+fn is_kv_macro(expr: &Expr) -> bool {
+    match expr {
+        Expr::Macro(expr) => expr
+            .mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "o"),
+        _ => false,
+    }
+}
+
+fn returns_impl_trait(output: &ReturnType) -> bool {
+    matches!(output, ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_)))
+}
@@ -0,0 +1,102 @@
+//! Futures spawned onto an actix [`Arbiter`] through [`ArbiterExt`] run
+//! under the logger that was current when they were spawned:
+//!
+//! ```
+//! # use slog::o;
+//! use std::sync::mpsc;
+//! use actix_rt::{Arbiter, System};
+//! use slog_scope_futures::actix::ArbiterExt;
+//!
+//! let system = System::new();
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let (tx, rx) = mpsc::channel();
+//!
+//! system.block_on(async {
+//!     let arbiter = Arbiter::new();
+//!
+//!     slog_scope::scope(&request, || {
+//!         let first = tx.clone();
+//!         arbiter.spawn_scoped(async move {
+//!             actix_rt::task::yield_now().await;
+//!             first.send(format!("{:?}", slog_scope::logger())).unwrap();
+//!         });
+//!         arbiter.handle().spawn_fn_scoped(move || tx.send(format!("{:?}", slog_scope::logger())).unwrap());
+//!     });
+//!
+//!     assert_eq!(rx.recv().unwrap(), "Logger(request_id)");
+//!     assert_eq!(rx.recv().unwrap(), "Logger(request_id)");
+//!
+//!     arbiter.stop();
+//!     arbiter.join().unwrap();
+//! });
+//! ```
+//!
+//! [`Arbiter`]: actix_rt::Arbiter
+//! [`ArbiterExt`]: crate::actix::ArbiterExt
+
+use std::future::Future;
+
+use actix_rt::{Arbiter, ArbiterHandle};
+use slog::Logger;
+
+use super::SlogScope;
+
+/// Scoped spawning for actix's [`Arbiter`] and [`ArbiterHandle`].
+///
+/// Like the methods they wrap, these return `false` if the arbiter has
+/// already stopped.
+pub trait ArbiterExt {
+    /// Spawn a future on the arbiter, running in the current scope.
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_scoped<F>(&self, future: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_scoped_with(slog_scope::logger(), future)
+    }
+
+    /// Spawn a future on the arbiter, running in `logger`'s scope.
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static;
+
+    /// Run a closure on the arbiter's thread in the current scope.
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_fn_scoped<F>(&self, f: F) -> bool
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.spawn_scoped(async move { f() })
+    }
+}
+
+impl ArbiterExt for Arbiter {
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn(SlogScope::new(logger, future))
+    }
+}
+
+impl ArbiterExt for ArbiterHandle {
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn(SlogScope::new(logger, future))
+    }
+}
+
+/// Spawn a future on `arbiter`, running in the current scope.
+///
+/// Equivalent to [`ArbiterExt::spawn_scoped`].
+pub fn arbiter_spawn_scoped<A, F>(arbiter: &A, future: F) -> bool
+where
+    A: ArbiterExt + ?Sized,
+    F: Future<Output = ()> + Send + 'static,
+{
+    arbiter.spawn_scoped(future)
+}
@@ -0,0 +1,110 @@
+//! [`SlogScopeMiddleware`] runs every request under a logger of its own,
+//! from the call into the service chain until the response is ready:
+//!
+//! ```
+//! # use slog::o;
+//! use actix_web::{test, web, App};
+//! use slog_scope_futures::actix_web::SlogScopeMiddleware;
+//!
+//! async fn handler() -> String {
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! actix_web::rt::System::new().block_on(async {
+//!     let root = slog::Logger::root(slog::Discard, o!());
+//!     let app = test::init_service(
+//!         App::new()
+//!             .wrap(SlogScopeMiddleware::new(move |req| {
+//!                 root.new(o!(
+//!                     "method" => req.method().to_string(),
+//!                     "path" => req.path().to_owned(),
+//!                 ))
+//!             }))
+//!             .route("/", web::get().to(handler)),
+//!     )
+//!     .await;
+//!
+//!     let req = test::TestRequest::get().uri("/").to_request();
+//!     let res = test::call_and_read_body(&app, req).await;
+//!     assert_eq!(res, "Logger(path, method)");
+//! });
+//! ```
+//!
+//! [`SlogScopeMiddleware`]: crate::actix_web::SlogScopeMiddleware
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use slog::Logger;
+
+use super::SlogScope;
+
+/// Middleware running each request under a logger of its own.
+///
+/// The logger is built by calling `make_logger` with the request. Neither it
+/// nor the downstream service futures need to be `Send`.
+pub struct SlogScopeMiddleware<M> {
+    make_logger: Rc<M>,
+}
+
+impl<M> SlogScopeMiddleware<M>
+where
+    M: Fn(&ServiceRequest) -> Logger,
+{
+    /// Create a middleware deriving request loggers with `make_logger`.
+    pub fn new(make_logger: M) -> Self {
+        SlogScopeMiddleware {
+            make_logger: Rc::new(make_logger),
+        }
+    }
+}
+
+impl<S, B, M> Transform<S, ServiceRequest> for SlogScopeMiddleware<M>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    M: Fn(&ServiceRequest) -> Logger,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SlogScopeService<S, M>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SlogScopeService {
+            service,
+            make_logger: self.make_logger.clone(),
+        }))
+    }
+}
+
+/// The service produced by [`SlogScopeMiddleware`].
+pub struct SlogScopeService<S, M> {
+    service: S,
+    make_logger: Rc<M>,
+}
+
+impl<S, B, M> Service<ServiceRequest> for SlogScopeService<S, M>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    M: Fn(&ServiceRequest) -> Logger,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = SlogScope<Logger, S::Future>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let logger = (self.make_logger)(&req);
+        let future = slog_scope::scope(&logger, || self.service.call(req));
+
+        SlogScope::new(logger, future)
+    }
+}
@@ -0,0 +1,117 @@
+//! Tasks spawned through [`ExecutorExt`] and [`LocalExecutorExt`] run under
+//! the logger that was current when they were spawned, on whichever thread
+//! ends up running them:
+//!
+//! ```
+//! # use slog::o;
+//! use async_executor::Executor;
+//! use futures_util::future;
+//! use slog_scope_futures::async_executor::ExecutorExt;
+//!
+//! let ex = Executor::new();
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//!
+//! let mut tasks: Vec<_> = slog_scope::scope(&request, || {
+//!     (0..4)
+//!         .map(|_| ex.spawn_scoped(async { format!("{:?}", slog_scope::logger()) }))
+//!         .collect()
+//! });
+//! let others = tasks.split_off(2);
+//!
+//! std::thread::scope(|s| {
+//!     let other = s.spawn(|| futures_executor::block_on(ex.run(future::join_all(others))));
+//!     let mut out = futures_executor::block_on(ex.run(future::join_all(tasks)));
+//!     out.extend(other.join().unwrap());
+//!
+//!     assert_eq!(out, ["Logger(request_id)"; 4]);
+//! });
+//! ```
+//!
+//! [`ExecutorExt`]: crate::async_executor::ExecutorExt
+//! [`LocalExecutorExt`]: crate::async_executor::LocalExecutorExt
+
+use std::future::Future;
+
+use async_executor::{Executor, LocalExecutor, Task};
+use slog::Logger;
+
+use super::SlogScope;
+
+/// Scoped spawning for `async_executor::Executor`.
+pub trait ExecutorExt<'a> {
+    /// Spawn a task running in the current scope.
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_scoped<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'a,
+        F::Output: Send + 'a,
+    {
+        self.spawn_scoped_with(slog_scope::logger(), future)
+    }
+
+    /// Spawn a task running in `logger`'s scope.
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'a,
+        F::Output: Send + 'a;
+}
+
+impl<'a> ExecutorExt<'a> for Executor<'a> {
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> Task<F::Output>
+    where
+        F: Future + Send + 'a,
+        F::Output: Send + 'a,
+    {
+        self.spawn(SlogScope::new(logger, future))
+    }
+}
+
+/// Scoped spawning for `async_executor::LocalExecutor`.
+///
+/// Spawned futures don't need to be `Send`:
+///
+/// ```
+/// # use slog::o;
+/// use std::rc::Rc;
+/// use async_executor::LocalExecutor;
+/// use slog_scope_futures::async_executor::LocalExecutorExt;
+///
+/// let ex = LocalExecutor::new();
+/// let window = slog::Logger::root(slog::Discard, o!("window" => 1));
+///
+/// let title = Rc::new("main");
+/// let task = slog_scope::scope(&window, || {
+///     ex.spawn_scoped(async move { format!("{} {:?}", title, slog_scope::logger()) })
+/// });
+///
+/// assert_eq!(futures_executor::block_on(ex.run(task)), "main Logger(window)");
+/// ```
+pub trait LocalExecutorExt<'a> {
+    /// Spawn a task running in the current scope.
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_scoped<F>(&self, future: F) -> Task<F::Output>
+    where
+        F: Future + 'a,
+        F::Output: 'a,
+    {
+        self.spawn_scoped_with(slog_scope::logger(), future)
+    }
+
+    /// Spawn a task running in `logger`'s scope.
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> Task<F::Output>
+    where
+        F: Future + 'a,
+        F::Output: 'a;
+}
+
+impl<'a> LocalExecutorExt<'a> for LocalExecutor<'a> {
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> Task<F::Output>
+    where
+        F: Future + 'a,
+        F::Output: 'a,
+    {
+        self.spawn(SlogScope::new(logger, future))
+    }
+}
@@ -0,0 +1,85 @@
+//! Slog scopes for nightly's `core::async_iter::AsyncIterator`.
+//!
+//! Needs a nightly compiler: on stable, the `nightly-async-iter` feature
+//! does nothing.
+//!
+//! ```
+//! #![feature(async_iterator)]
+//! # use slog::{info, o};
+//! use std::{async_iter::AsyncIterator, pin::Pin, task::{Context, Poll}};
+//! use slog_scope_futures::{async_iter::AsyncIteratorExt, test_util::capture};
+//!
+//! /// Counts to 2, logging each step.
+//! struct Counter(u32);
+//!
+//! impl AsyncIterator for Counter {
+//!     type Item = u32;
+//!
+//!     fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u32>> {
+//!         self.0 += 1;
+//!         info!(slog_scope::logger(), "step"; "n" => self.0);
+//!         Poll::Ready((self.0 <= 2).then_some(self.0))
+//!     }
+//! }
+//!
+//! let (logger, records) = capture();
+//! let logger = logger.new(o!("job" => 1));
+//!
+//! let mut counter = Counter(0).with_logger(logger);
+//! let counted = futures_executor::block_on(async {
+//!     let mut counted = Vec::new();
+//!     while let Some(n) = std::future::poll_fn(|cx| Pin::new(&mut counter).poll_next(cx)).await {
+//!         counted.push(n);
+//!     }
+//!     counted
+//! });
+//!
+//! assert_eq!(counted, [1, 2]);
+//! let records = records.records();
+//! let steps: Vec<_> = records.iter().map(|r| r.kv("n").unwrap()).collect();
+//! assert_eq!(steps, ["1", "2", "3"]);
+//! assert!(records.iter().all(|r| r.msg() == "step" && r.kv("job") == Some("1")));
+//! ```
+
+use std::{
+    async_iter::AsyncIterator,
+    borrow::Borrow,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use slog::Logger;
+
+use super::SlogScope;
+
+impl<L, S> AsyncIterator for SlogScope<L, S>
+where
+    S: AsyncIterator,
+    L: Borrow<Logger>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.scoped(|inner| inner.poll_next(cx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.get_ref().size_hint()
+    }
+}
+
+/// Convenience trait for wrapping an `AsyncIterator` in a slog scope via
+/// method chaining.
+///
+/// Automatically implemented for all `AsyncIterator`s.
+pub trait AsyncIteratorExt: AsyncIterator + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<S> AsyncIteratorExt for S where S: AsyncIterator {}
@@ -0,0 +1,67 @@
+//! Tasks spawned onto async-std's executor run under the logger that was
+//! current when they were spawned:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::async_std::{spawn_blocking_scoped, spawn_scoped};
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//!
+//! async_std::task::block_on(async {
+//!     let (task, blocking) = slog_scope::scope(&request, || {
+//!         let task = spawn_scoped(async { format!("{:?}", slog_scope::logger()) });
+//!         let blocking = spawn_blocking_scoped(|| format!("{:?}", slog_scope::logger()));
+//!         (task, blocking)
+//!     });
+//!
+//!     assert_eq!(task.await, "Logger(request_id)");
+//!     assert_eq!(blocking.await, "Logger(request_id)");
+//! });
+//! ```
+
+use std::future::Future;
+
+use async_std::task::{self, JoinHandle};
+use slog::Logger;
+
+use super::{closure::ScopedFnOnce, SlogScope};
+
+/// Spawn a task running in the current scope.
+///
+/// The current logger is captured when this is called.
+pub fn spawn_scoped<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    spawn_scoped_with(slog_scope::logger(), future)
+}
+
+/// Spawn a task running in `logger`'s scope.
+pub fn spawn_scoped_with<F>(logger: Logger, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    task::spawn(SlogScope::new(logger, future))
+}
+
+/// Run a blocking closure on a separate thread in the current scope.
+///
+/// The current logger is captured when this is called.
+pub fn spawn_blocking_scoped<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    spawn_blocking_scoped_with(slog_scope::logger(), f)
+}
+
+/// Run a blocking closure on a separate thread in `logger`'s scope.
+pub fn spawn_blocking_scoped_with<F, R>(logger: Logger, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    task::spawn_blocking(ScopedFnOnce::new(logger, f).into_fn())
+}
@@ -0,0 +1,153 @@
+//! [`ScopeLayer`] derives a logger for each request and runs the rest of the
+//! stack under it, including the streaming of the response body. Handlers
+//! can log through `slog_scope` as usual, or grab the request's logger with
+//! the [`ScopedLogger`] extractor.
+//!
+//! ```
+//! # use slog::o;
+//! use axum::{body::Body, routing::get, Router};
+//! use futures_util::stream;
+//! use http_body_util::BodyExt;
+//! use slog_scope_futures::axum::{ScopeLayer, ScopedLogger};
+//! use tower::ServiceExt;
+//!
+//! async fn handler(ScopedLogger(logger): ScopedLogger) -> Body {
+//!     assert_eq!(format!("{:?}", logger), format!("{:?}", slog_scope::logger()));
+//!
+//!     Body::from_stream(stream::poll_fn(|_| {
+//!         let scope = format!("{:?}", slog_scope::logger());
+//!         std::task::Poll::Ready(Some(Ok::<_, std::io::Error>(scope)))
+//!     }))
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!());
+//! let app = Router::new()
+//!     .route("/", get(handler))
+//!     .layer(ScopeLayer::new(logger));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! runtime.block_on(async {
+//!     let req = http::Request::get("/").body(Body::empty()).unwrap();
+//!     let mut body = app.oneshot(req).await.unwrap().into_body();
+//!
+//!     let chunk = body.frame().await.unwrap().unwrap().into_data().unwrap();
+//!     assert_eq!(chunk, "Logger(request_id, path, method)");
+//! });
+//! ```
+//!
+//! [`ScopeLayer`]: crate::axum::ScopeLayer
+//! [`ScopedLogger`]: crate::axum::ScopedLogger
+
+use std::{
+    convert::Infallible,
+    sync::atomic::{AtomicU64, Ordering},
+    task::{Context, Poll},
+};
+
+use axum::extract::FromRequestParts;
+use http::{request::Parts, Request, Response};
+use slog::{o, Logger};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use super::{tower::ResponseFuture, SlogScope};
+
+/// Header a request id is taken from, if present.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// A `Layer` running each request under a child logger of its own.
+///
+/// The child logger carries `method`, `path` and `request_id` KVs, the
+/// latter taken from the `x-request-id` header or generated if the header is
+/// missing. It's also inserted into the request extensions, making it
+/// available to [`ScopedLogger`] and other layers.
+#[derive(Clone, Debug)]
+pub struct ScopeLayer {
+    logger: Logger,
+}
+
+impl ScopeLayer {
+    /// Create a layer deriving request loggers from `logger`.
+    pub fn new(logger: Logger) -> Self {
+        ScopeLayer { logger }
+    }
+}
+
+impl<S> Layer<S> for ScopeLayer {
+    type Service = Scope<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Scope {
+            inner,
+            logger: self.logger.clone(),
+        }
+    }
+}
+
+/// A service running each request under a child logger of its own.
+///
+/// See [`ScopeLayer`].
+#[derive(Clone, Debug)]
+pub struct Scope<S> {
+    inner: S,
+    logger: Logger,
+}
+
+impl<S, B, ResBody> Service<Request<B>> for Scope<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>>,
+{
+    type Response = Response<SlogScope<Logger, ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let inner = &mut self.inner;
+
+        slog_scope::scope(&self.logger, || inner.poll_ready(cx))
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let request_id = match req.headers().get(REQUEST_ID_HEADER) {
+            Some(id) => String::from_utf8_lossy(id.as_bytes()).into_owned(),
+            None => next_request_id().to_string(),
+        };
+        let logger = self.logger.new(o!(
+            "method" => req.method().to_string(),
+            "path" => req.uri().path().to_owned(),
+            "request_id" => request_id,
+        ));
+        req.extensions_mut().insert(logger.clone());
+
+        let inner = &mut self.inner;
+        let future = slog_scope::scope(&logger, || inner.call(req));
+
+        ResponseFuture::new(SlogScope::new(logger, future))
+    }
+}
+
+fn next_request_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Extractor for the logger of the current request.
+///
+/// This is the logger set up by [`ScopeLayer`], falling back to the current
+/// scope's logger if the layer isn't in use.
+#[derive(Clone, Debug)]
+pub struct ScopedLogger(pub Logger);
+
+impl<S> FromRequestParts<S> for ScopedLogger
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let logger = parts.extensions.get::<Logger>().cloned();
+
+        Ok(ScopedLogger(logger.unwrap_or_else(slog_scope::logger)))
+    }
+}
@@ -0,0 +1,299 @@
+//! Channels carrying the sender's logger along with each message.
+//!
+//! Messages sent through a [`ScopedSender`] or [`ScopedUnboundedSender`]
+//! are paired with the logger that was current when they were sent, and the
+//! [`ScopedReceiver`] can run the handling of each in that logger's scope:
+//!
+//! ```
+//! # use slog::o;
+//! use futures_util::future;
+//! use slog_scope_futures::{channel::scoped_channel, FutureExt};
+//!
+//! let (tx, rx) = scoped_channel::<u32>(8);
+//!
+//! let producers: Vec<_> = (1..=2)
+//!     .map(|id| {
+//!         let mut tx = tx.clone();
+//!         let request = slog::Logger::root(slog::Discard, o!("request_id" => id));
+//!         async move { tx.send(id).await.unwrap() }.with_logger(request)
+//!     })
+//!     .collect();
+//! drop(tx);
+//!
+//! let handled = futures_executor::block_on(async {
+//!     future::join_all(producers).await;
+//!
+//!     let mut handled = Vec::new();
+//!     rx.for_each_scoped(|id| {
+//!         handled.push((id, format!("{:?}", slog_scope::logger())));
+//!         future::ready(())
+//!     })
+//!     .await;
+//!     handled
+//! });
+//!
+//! assert_eq!(handled, [(1, "Logger(request_id)".to_owned()), (2, "Logger(request_id)".to_owned())]);
+//! ```
+//!
+//! [`ScopedSender`]: crate::channel::ScopedSender
+//! [`ScopedUnboundedSender`]: crate::channel::ScopedUnboundedSender
+//! [`ScopedReceiver`]: crate::channel::ScopedReceiver
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_channel::mpsc::{self, SendError, TrySendError};
+use futures_core::{FusedStream, Stream};
+use futures_sink::Sink;
+use pin_project_lite::pin_project;
+use slog::Logger;
+
+use super::SlogScope;
+
+/// Create a bounded channel carrying the sender's logger with each message.
+///
+/// As with `futures::channel::mpsc::channel`, each sender can send one
+/// message on top of the `buffer` shared by all of them before waiting.
+pub fn scoped_channel<T>(buffer: usize) -> (ScopedSender<T>, ScopedReceiver<T>) {
+    let (tx, rx) = mpsc::channel(buffer);
+
+    (ScopedSender(tx), ScopedReceiver(Receiver::Bounded(rx)))
+}
+
+/// Create an unbounded channel carrying the sender's logger with each
+/// message.
+pub fn scoped_unbounded<T>() -> (ScopedUnboundedSender<T>, ScopedReceiver<T>) {
+    let (tx, rx) = mpsc::unbounded();
+
+    (
+        ScopedUnboundedSender(tx),
+        ScopedReceiver(Receiver::Unbounded(rx)),
+    )
+}
+
+/// The sending half of a [`scoped_channel`].
+///
+/// Also a `Sink`, pairing each item with the logger current when
+/// `start_send` is called.
+#[derive(Debug)]
+pub struct ScopedSender<T>(mpsc::Sender<(Logger, T)>);
+
+impl<T> ScopedSender<T> {
+    /// Send `item` along with the current logger, waiting for room in the
+    /// channel.
+    ///
+    /// The logger is captured when this is called.
+    pub fn send(&mut self, item: T) -> impl Future<Output = Result<(), SendError>> + '_ {
+        let mut msg = Some((slog_scope::logger(), item));
+        std::future::poll_fn(move |cx| {
+            ready!(self.0.poll_ready(cx))?;
+            let msg = msg.take().expect("send polled after completion");
+            Poll::Ready(self.0.start_send(msg))
+        })
+    }
+
+    /// Send `item` along with the current logger, if there's room in the
+    /// channel.
+    pub fn try_send(&mut self, item: T) -> Result<(), TrySendError<(Logger, T)>> {
+        self.0.try_send((slog_scope::logger(), item))
+    }
+
+    /// Check whether the receiver is gone.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    /// Close the channel, for all senders.
+    pub fn close_channel(&mut self) {
+        self.0.close_channel()
+    }
+}
+
+impl<T> Clone for ScopedSender<T> {
+    fn clone(&self) -> Self {
+        ScopedSender(self.0.clone())
+    }
+}
+
+impl<T> Sink<T> for ScopedSender<T> {
+    type Error = SendError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.0.start_send((slog_scope::logger(), item))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.0).poll_close(cx)
+    }
+}
+
+/// The sending half of a [`scoped_unbounded`] channel.
+///
+/// Also a `Sink`, pairing each item with the logger current when
+/// `start_send` is called.
+#[derive(Debug)]
+pub struct ScopedUnboundedSender<T>(mpsc::UnboundedSender<(Logger, T)>);
+
+impl<T> ScopedUnboundedSender<T> {
+    /// Send `item` along with the current logger.
+    pub fn unbounded_send(&self, item: T) -> Result<(), TrySendError<(Logger, T)>> {
+        self.0.unbounded_send((slog_scope::logger(), item))
+    }
+
+    /// Check whether the receiver is gone.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    /// Close the channel, for all senders.
+    pub fn close_channel(&self) {
+        self.0.close_channel()
+    }
+}
+
+impl<T> Clone for ScopedUnboundedSender<T> {
+    fn clone(&self) -> Self {
+        ScopedUnboundedSender(self.0.clone())
+    }
+}
+
+impl<T> Sink<T> for ScopedUnboundedSender<T> {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &self.0).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        Pin::new(&mut &self.0).start_send((slog_scope::logger(), item))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &self.0).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &self.0).poll_close(cx)
+    }
+}
+
+#[derive(Debug)]
+enum Receiver<T> {
+    Bounded(mpsc::Receiver<(Logger, T)>),
+    Unbounded(mpsc::UnboundedReceiver<(Logger, T)>),
+}
+
+/// The receiving half of a [`scoped_channel`] or [`scoped_unbounded`]
+/// channel.
+///
+/// As a `Stream`, it yields each message with the logger it was sent with.
+#[derive(Debug)]
+pub struct ScopedReceiver<T>(Receiver<T>);
+
+impl<T> ScopedReceiver<T> {
+    /// Run `f` on each message, with both the call and the future it returns
+    /// in the scope of the message's logger.
+    ///
+    /// Messages are handled one at a time, until all senders are gone.
+    pub fn for_each_scoped<F, Fut>(self, f: F) -> ForEachScoped<T, F, Fut>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        ForEachScoped::new(self, f)
+    }
+
+    /// Close the channel, letting messages already sent be received.
+    pub fn close(&mut self) {
+        match &mut self.0 {
+            Receiver::Bounded(rx) => rx.close(),
+            Receiver::Unbounded(rx) => rx.close(),
+        }
+    }
+}
+
+impl<T> Stream for ScopedReceiver<T> {
+    type Item = (Logger, T);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match &mut self.0 {
+            Receiver::Bounded(rx) => Pin::new(rx).poll_next(cx),
+            Receiver::Unbounded(rx) => Pin::new(rx).poll_next(cx),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            Receiver::Bounded(rx) => rx.size_hint(),
+            Receiver::Unbounded(rx) => rx.size_hint(),
+        }
+    }
+}
+
+impl<T> FusedStream for ScopedReceiver<T> {
+    fn is_terminated(&self) -> bool {
+        match &self.0 {
+            Receiver::Bounded(rx) => rx.is_terminated(),
+            Receiver::Unbounded(rx) => rx.is_terminated(),
+        }
+    }
+}
+
+pin_project! {
+    /// Future for [`ScopedReceiver::for_each_scoped`].
+    pub struct ForEachScoped<T, F, Fut> {
+        receiver: ScopedReceiver<T>,
+        f: F,
+        #[pin]
+        handling: Option<SlogScope<Logger, Fut>>,
+    }
+}
+
+impl<T, F, Fut> ForEachScoped<T, F, Fut> {
+    pub(crate) fn new(receiver: ScopedReceiver<T>, f: F) -> Self {
+        ForEachScoped {
+            receiver,
+            f,
+            handling: None,
+        }
+    }
+}
+
+impl<T, F, Fut> Future for ForEachScoped<T, F, Fut>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(handling) = this.handling.as_mut().as_pin_mut() {
+                ready!(handling.poll(cx));
+                this.handling.set(None);
+            }
+
+            match ready!(Pin::new(&mut *this.receiver).poll_next(cx)) {
+                Some((logger, item)) => {
+                    let f = &mut *this.f;
+                    let fut = slog_scope::scope(&logger, || f(item));
+                    this.handling.set(Some(SlogScope::new(logger, fut)));
+                }
+                None => return Poll::Ready(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! Synchronous code often ends up running away from the scope it was set up
+//! in, e.g. on `spawn_blocking` threads. Wrapping the closure itself carries
+//! the logger along:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::closure::FnOnceExt;
+//!
+//! fn do_sync_work() -> String {
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("job" => 1));
+//! let work = do_sync_work.with_logger(logger);
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let out = runtime.block_on(async { tokio::task::spawn_blocking(work.into_fn()).await });
+//! assert_eq!(out.unwrap(), "Logger(job)");
+//! ```
+
+use std::{borrow::Borrow, future::Future};
+
+use slog::Logger;
+
+use super::SlogScope;
+
+/// A `FnOnce` closure wrapped in a slog scope.
+///
+/// Stable Rust doesn't allow implementing `FnOnce` for it directly, so use
+/// [`call`](Self::call), or [`into_fn`](Self::into_fn) where a closure is
+/// expected:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::closure::FnOnceExt;
+///
+/// fn on_shutdown(callback: impl FnOnce() -> String) -> String {
+///     callback()
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+/// let callback = || format!("{:?}", slog_scope::logger());
+///
+/// assert_eq!(callback.with_logger(&logger).call(), "Logger(conn)");
+/// assert_eq!(on_shutdown(callback.with_logger(&logger).into_fn()), "Logger(conn)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedFnOnce<L, F> {
+    logger: L,
+    f: F,
+}
+
+impl<L, F> ScopedFnOnce<L, F>
+where
+    L: Borrow<Logger>,
+{
+    /// Wrap a `FnOnce` closure in a slog scope.
+    pub fn new(logger: L, f: F) -> Self {
+        ScopedFnOnce { logger, f }
+    }
+
+    /// Call the closure with the logger's scope active.
+    pub fn call<R>(self) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        slog_scope::scope(self.logger.borrow(), self.f)
+    }
+
+    /// Turn the wrapper into a plain closure.
+    pub fn into_fn<R>(self) -> impl FnOnce() -> R
+    where
+        F: FnOnce() -> R,
+    {
+        move || self.call()
+    }
+}
+
+/// Convenience trait for wrapping a `FnOnce` closure in a slog scope via
+/// method chaining.
+///
+/// Automatically implemented for all `FnOnce()` closures.
+pub trait FnOnceExt<R>: FnOnce() -> R + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> ScopedFnOnce<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        ScopedFnOnce::new(logger, self)
+    }
+}
+
+impl<F, R> FnOnceExt<R> for F where F: FnOnce() -> R {}
+
+/// A `Fn` or `FnMut` callable wrapped in a slog scope.
+///
+/// Every call enters the scope anew, from whichever thread it's made on.
+/// Arguments are passed as a tuple, for callables taking up to 3 of them:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::closure::ScopedFn;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("watcher" => 1));
+/// let on_event = ScopedFn::new(logger, |path: &str, kind: u8| {
+///     format!("{} {} {:?}", path, kind, slog_scope::logger())
+/// });
+///
+/// std::thread::scope(|s| {
+///     let threads = [s.spawn(|| on_event.call(("a", 1))), s.spawn(|| on_event.call(("b", 2)))];
+///     let [a, b] = threads.map(|t| t.join().unwrap());
+///
+///     assert_eq!(a, "a 1 Logger(watcher)");
+///     assert_eq!(b, "b 2 Logger(watcher)");
+/// });
+///
+/// let mut count = 0;
+/// let mut on_tick = ScopedFn::new(slog_scope::logger(), || count += 1);
+/// on_tick.call_mut(());
+/// on_tick.call_mut(());
+/// drop(on_tick);
+/// assert_eq!(count, 2);
+/// ```
+///
+/// It can also be turned into a boxed closure, for libraries expecting one:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::closure::ScopedFn;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("hook" => 1));
+/// let hook = ScopedFn::new(logger, |code: i32| format!("{} {:?}", code, slog_scope::logger()));
+///
+/// let hook: Box<dyn Fn(i32) -> String + Send + Sync> = hook.into_boxed_fn();
+/// assert_eq!(hook(7), "7 Logger(hook)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedFn<L, F> {
+    logger: L,
+    f: F,
+}
+
+impl<L, F> ScopedFn<L, F>
+where
+    L: Borrow<Logger>,
+{
+    /// Wrap a callable in a slog scope.
+    pub fn new(logger: L, f: F) -> Self {
+        ScopedFn { logger, f }
+    }
+
+    /// Call a `Fn` callable with the logger's scope active.
+    pub fn call<Args>(&self, args: Args) -> F::Output
+    where
+        F: Call<Args>,
+    {
+        let f = &self.f;
+
+        slog_scope::scope(self.logger.borrow(), || f.call(args))
+    }
+
+    /// Call a `FnMut` callable with the logger's scope active.
+    pub fn call_mut<Args>(&mut self, args: Args) -> F::Output
+    where
+        F: CallMut<Args>,
+    {
+        let f = &mut self.f;
+
+        slog_scope::scope(self.logger.borrow(), || f.call_mut(args))
+    }
+
+    /// Turn the wrapper into a boxed `Fn` closure.
+    pub fn into_boxed_fn<Args>(self) -> Box<<Self as IntoBoxedFn<Args>>::Fn>
+    where
+        Self: IntoBoxedFn<Args>,
+    {
+        IntoBoxedFn::into_boxed_fn(self)
+    }
+}
+
+/// `Fn` callables taking their arguments as a tuple.
+///
+/// Implemented for callables taking up to 3 arguments.
+pub trait Call<Args> {
+    /// The callable's return type.
+    type Output;
+
+    /// Call `self` with `args`.
+    fn call(&self, args: Args) -> Self::Output;
+}
+
+/// `FnMut` callables taking their arguments as a tuple.
+///
+/// Implemented for callables taking up to 3 arguments.
+pub trait CallMut<Args> {
+    /// The callable's return type.
+    type Output;
+
+    /// Call `self` with `args`.
+    fn call_mut(&mut self, args: Args) -> Self::Output;
+}
+
+/// Conversion of a [`ScopedFn`] into a boxed closure taking `Args`.
+pub trait IntoBoxedFn<Args> {
+    /// The closure's type.
+    type Fn: ?Sized;
+
+    /// Box `self` into a closure.
+    fn into_boxed_fn(self) -> Box<Self::Fn>;
+}
+
+macro_rules! impl_call {
+    ($($arg:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<F, R, $($arg),*> Call<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R,
+        {
+            type Output = R;
+
+            fn call(&self, ($($arg,)*): ($($arg,)*)) -> Self::Output {
+                self($($arg),*)
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<F, R, $($arg),*> CallMut<($($arg,)*)> for F
+        where
+            F: FnMut($($arg),*) -> R,
+        {
+            type Output = R;
+
+            fn call_mut(&mut self, ($($arg,)*): ($($arg,)*)) -> Self::Output {
+                self($($arg),*)
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<L, F, R, $($arg),*> IntoBoxedFn<($($arg,)*)> for ScopedFn<L, F>
+        where
+            L: Borrow<Logger> + Send + Sync + 'static,
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+        {
+            type Fn = dyn Fn($($arg),*) -> R + Send + Sync;
+
+            fn into_boxed_fn(self) -> Box<Self::Fn> {
+                Box::new(move |$($arg),*| self.call(($($arg,)*)))
+            }
+        }
+    };
+}
+
+impl_call!();
+impl_call!(A);
+impl_call!(A, B);
+impl_call!(A, B, C);
+
+/// Wrap an async function so that every future it returns runs under a
+/// logger of its own, built from its argument by `make_logger`.
+///
+/// Unlike wrapping a single future, the logger is rebuilt on every call,
+/// which suits handler factories such as `hyper::service::service_fn` or
+/// `tower::service_fn`, called once per request:
+///
+/// ```
+/// # use slog::o;
+/// use std::convert::Infallible;
+/// use tower::{service_fn, Service, ServiceExt};
+/// use slog_scope_futures::closure::wrap_async_fn;
+///
+/// struct Request {
+///     id: u32,
+/// }
+///
+/// async fn handle(req: Request) -> Result<String, Infallible> {
+///     Ok(format!("{} {:?}", req.id, slog_scope::logger()))
+/// }
+///
+/// let server = slog::Logger::root(slog::Discard, o!("server" => 1));
+/// let handler = wrap_async_fn(handle, |req: &Request| server.new(o!("request_id" => req.id)));
+/// let mut service = service_fn(handler);
+///
+/// futures_executor::block_on(async {
+///     for id in 1..=2 {
+///         let out = service.ready().await.unwrap().call(Request { id }).await;
+///         assert_eq!(out.unwrap(), format!("{} Logger(request_id, server)", id));
+///     }
+/// });
+/// ```
+///
+/// With hyper, pass the wrapped handler to `service_fn` the same way, e.g.
+/// `service_fn(wrap_async_fn(handle, |req: &Request<Incoming>| ...))`.
+pub fn wrap_async_fn<A, F, Fut, M>(f: F, make_logger: M) -> impl Fn(A) -> SlogScope<Logger, Fut>
+where
+    F: Fn(A) -> Fut,
+    Fut: Future,
+    M: Fn(&A) -> Logger,
+{
+    move |arg| {
+        let logger = make_logger(&arg);
+        SlogScope::new(logger, f(arg))
+    }
+}
@@ -1,29 +1,29 @@
+//! The futures 0.1 extension trait's former home.
+//!
+//! Use [`futures01::FutureExt01`](crate::futures01::FutureExt01) instead.
+//! Imports from here still work, with a warning:
+//!
+//! ```compile_fail
+//! #![deny(deprecated)]
+//!
+//! use slog_scope_futures::future01::FutureExt;
+//! ```
+#![allow(deprecated)]
+
 use std::borrow::Borrow;
 
-use futures::{Future, Poll};
+use futures::Future;
 use slog::Logger;
 
 use super::SlogScope;
 
-impl<L, F> Future for SlogScope<L, F>
-where
-    F: Future,
-    L: Borrow<Logger>,
-{
-    type Item = F::Item;
-    type Error = F::Error;
-
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        let inner = &mut self.inner;
-        let logger = &self.logger;
-
-        slog_scope::scope(logger.borrow(), || inner.poll())
-    }
-}
-
 /// Convenience trait for wrapping a `0.1 Future` in a slog scope via method chaining.
 ///
 /// Automatically implemented for all `0.1 Future`s.
+#[deprecated(
+    since = "0.2.0",
+    note = "use `slog_scope_futures::futures01::FutureExt01` instead"
+)]
 pub trait FutureExt: Future + Sized {
     /// Wrap `self` in a slog scope
     fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
@@ -1,34 +1,26 @@
-use std::{
-    borrow::Borrow,
-    future::Future,
-    pin::Pin,
-    task::{Context, Poll},
-};
+//! The `std::future` extension trait's former home.
+//!
+//! Use [`FutureExt`](crate::FutureExt) from the crate root instead, and the
+//! [`futures03`](crate::futures03) module for `futures` 0.3 traits. Imports
+//! from here still work, with a warning:
+//!
+//! ```compile_fail
+//! #![deny(deprecated)]
+//!
+//! use slog_scope_futures::future03::FutureExt;
+//! ```
+#![allow(deprecated)]
+
+use std::{borrow::Borrow, future::Future};
 
 use slog::Logger;
 
 use super::SlogScope;
 
-impl<L, F> Future for SlogScope<L, F>
-where
-    F: Future,
-    L: Borrow<Logger>,
-{
-    type Output = F::Output;
-
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        // Safety: We're not moving any of this, the inner future, or the logger.
-        let this = unsafe { self.get_unchecked_mut() };
-        let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
-        let logger = &this.logger;
-
-        slog_scope::scope(logger.borrow(), || inner.poll(cx))
-    }
-}
-
 /// Convenience trait for wrapping a `std::future` in a slog scope via method chaining.
 ///
 /// Automatically implemented for all `std::future`s.
+#[deprecated(since = "0.2.0", note = "use `slog_scope_futures::FutureExt` instead")]
 pub trait FutureExt: Future + Sized {
     /// Wrap `self` in a slog scope
     fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
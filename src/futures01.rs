@@ -0,0 +1,193 @@
+//! The extension traits and their methods are suffixed with `01`, so that
+//! they can be imported alongside the `std::future` and `futures` 0.3 ones,
+//! and `with_logger` keeps meaning the same thing on types implementing both
+//! kinds of `Future`:
+//!
+//! ```
+//! # use slog::o;
+//! use futures::Future as _;
+//! use slog_scope_futures::{futures01::FutureExt01, FutureExt};
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//!
+//! let fut03 = async { format!("{:?}", slog_scope::logger()) }.with_logger(&logger);
+//! assert_eq!(futures_executor::block_on(fut03), "Logger(request_id)");
+//!
+//! let fut01 = futures::future::lazy(|| Ok::<_, ()>(format!("{:?}", slog_scope::logger())));
+//! assert_eq!(fut01.with_logger01(&logger).wait(), Ok("Logger(request_id)".to_owned()));
+//! ```
+
+use std::borrow::Borrow;
+
+use futures::{Future, Poll, Sink, StartSend, Stream};
+use slog::Logger;
+
+use super::SlogScope;
+
+impl<L, F> Future for SlogScope<L, F>
+where
+    F: Future,
+    L: Borrow<Logger>,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.scoped_mut(|inner| inner.poll())
+    }
+}
+
+impl<L, S> Stream for SlogScope<L, S>
+where
+    S: Stream,
+    L: Borrow<Logger>,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.scoped_mut(|inner| inner.poll())
+    }
+}
+
+impl<L, S> Sink for SlogScope<L, S>
+where
+    S: Sink,
+    L: Borrow<Logger>,
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        self.scoped_mut(|inner| inner.start_send(item))
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        self.scoped_mut(|inner| inner.poll_complete())
+    }
+
+    fn close(&mut self) -> Poll<(), Self::SinkError> {
+        self.scoped_mut(|inner| inner.close())
+    }
+}
+
+/// Convenience trait for wrapping a `0.1 Future` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `0.1 Future`s.
+pub trait FutureExt01: Future + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger01<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<F> FutureExt01 for F where F: Future {}
+
+/// Convenience trait for wrapping a `0.1 Stream` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `0.1 Stream`s.
+///
+/// ```
+/// # use slog::o;
+/// use futures::{stream, sync::mpsc, Async, Future, Sink, Stream};
+/// use slog_scope_futures::futures01::StreamExt01;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+///
+/// let (tx, mut rx) = mpsc::channel(2);
+/// tx.send_all(stream::iter_ok(vec![1, 2])).wait().unwrap();
+///
+/// let mut polls = Vec::new();
+/// let messages = stream::poll_fn(|| {
+///     let next = rx.poll();
+///     polls.push((format!("{:?}", slog_scope::logger()), next));
+///     next
+/// })
+/// .with_logger01(logger)
+/// .collect()
+/// .wait()
+/// .unwrap();
+///
+/// assert_eq!(messages, [1, 2]);
+/// assert_eq!(polls.last().unwrap().1, Ok(Async::Ready(None)));
+/// assert!(polls.iter().all(|(scope, _)| scope == "Logger(conn)"));
+/// ```
+pub trait StreamExt01: Stream + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger01<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<S> StreamExt01 for S where S: Stream {}
+
+/// Convenience trait for wrapping a `0.1 Sink` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `0.1 Sink`s.
+///
+/// ```
+/// # use slog::o;
+/// # use std::sync::{Arc, Mutex};
+/// use futures::{Async, AsyncSink, Future, Poll, Sink, StartSend};
+/// use slog_scope_futures::futures01::SinkExt01;
+///
+/// /// A sink recording the active scope whenever it's driven.
+/// struct Recorder(Arc<Mutex<Vec<String>>>);
+///
+/// impl Recorder {
+///     fn record(&mut self) {
+///         self.0.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+///     }
+/// }
+///
+/// impl Sink for Recorder {
+///     type SinkItem = u32;
+///     type SinkError = ();
+///
+///     fn start_send(&mut self, _: u32) -> StartSend<u32, ()> {
+///         self.record();
+///         Ok(AsyncSink::Ready)
+///     }
+///
+///     fn poll_complete(&mut self) -> Poll<(), ()> {
+///         self.record();
+///         Ok(Async::Ready(()))
+///     }
+///
+///     fn close(&mut self) -> Poll<(), ()> {
+///         self.record();
+///         Ok(Async::Ready(()))
+///     }
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+/// let records = Arc::new(Mutex::new(Vec::new()));
+///
+/// let mut sink = Recorder(records.clone())
+///     .with_logger01(logger)
+///     .send(1)
+///     .wait()
+///     .unwrap();
+/// sink.close().unwrap();
+///
+/// let records = records.lock().unwrap();
+/// assert_eq!(records.len(), 3);
+/// assert!(records.iter().all(|r| r == "Logger(conn)"));
+/// ```
+pub trait SinkExt01: Sink + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger01<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<S> SinkExt01 for S where S: Sink {}
@@ -0,0 +1,1246 @@
+//! Implementations of the `futures` 0.3 traits for [`SlogScope`], and
+//! extension traits for wrapping streams, sinks, I/O objects and spawners.
+//!
+//! [`SlogScope`]: crate::SlogScope
+
+use std::{
+    borrow::Borrow,
+    future::Future,
+    io::{self, IoSlice, IoSliceMut, SeekFrom},
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{ready, Context, Poll},
+};
+
+use futures_core::{
+    stream::{BoxStream, LocalBoxStream},
+    FusedFuture, FusedStream, Stream,
+};
+use futures_io::{AsyncBufRead, AsyncRead, AsyncSeek, AsyncWrite};
+use futures_sink::Sink;
+use futures_task::{FutureObj, LocalFutureObj, LocalSpawn, Spawn, SpawnError};
+use futures_util::{
+    future,
+    stream::{self, FuturesUnordered},
+};
+use pin_project_lite::pin_project;
+use slog::{o, Logger};
+
+use super::SlogScope;
+
+/// Lets a scoped future be used directly in `select!`.
+///
+/// `is_terminated` only inspects the inner future's state, so it's forwarded
+/// without entering the scope.
+///
+/// ```
+/// # use slog::o;
+/// use futures_util::{future, select};
+/// use slog_scope_futures::FutureExt;
+///
+/// let first = slog::Logger::root(slog::Discard, o!("first" => 1));
+/// let second = slog::Logger::root(slog::Discard, o!("second" => 2));
+///
+/// let mut a = future::lazy(|_| format!("{:?}", slog_scope::logger())).with_logger(first);
+/// let mut b = future::lazy(|_| format!("{:?}", slog_scope::logger())).with_logger(second);
+///
+/// futures_executor::block_on(async {
+///     for _ in 0..2 {
+///         select! {
+///             scope = a => assert_eq!(scope, "Logger(first)"),
+///             scope = b => assert_eq!(scope, "Logger(second)"),
+///         }
+///     }
+/// });
+/// ```
+impl<L, F> FusedFuture for SlogScope<L, F>
+where
+    F: FusedFuture,
+    L: Borrow<Logger>,
+{
+    fn is_terminated(&self) -> bool {
+        self.get_ref().is_terminated()
+    }
+}
+
+impl<L, S> Stream for SlogScope<L, S>
+where
+    S: Stream,
+    L: Borrow<Logger>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_next_instrumented(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.get_ref().size_hint()
+    }
+}
+
+/// Lets a scoped stream be used with `select!` and `select_next_some`.
+///
+/// As with `FusedFuture`, `is_terminated` is forwarded without entering the
+/// scope.
+///
+/// ```
+/// # use slog::o;
+/// use futures_util::{select, stream::{self, StreamExt as _}};
+/// use slog_scope_futures::futures03::StreamExt;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+///
+/// let mut items = stream::iter(1..=3)
+///     .map(|i| (i, format!("{:?}", slog_scope::logger())))
+///     .fuse()
+///     .with_logger(logger);
+///
+/// futures_executor::block_on(async {
+///     let mut seen = 0;
+///     loop {
+///         select! {
+///             (i, scope) = items.select_next_some() => {
+///                 assert_eq!(scope, "Logger(conn)");
+///                 seen += i;
+///             }
+///             complete => break,
+///         }
+///     }
+///     assert_eq!(seen, 6);
+/// });
+/// ```
+impl<L, S> FusedStream for SlogScope<L, S>
+where
+    S: FusedStream,
+    L: Borrow<Logger>,
+{
+    fn is_terminated(&self) -> bool {
+        self.get_ref().is_terminated()
+    }
+}
+
+impl<L, S, Item> Sink<Item> for SlogScope<L, S>
+where
+    S: Sink<Item>,
+    L: Borrow<Logger>,
+{
+    type Error = S::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.scoped(|inner| inner.poll_ready(cx))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        self.scoped(|inner| inner.start_send(item))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.scoped(|inner| inner.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.scoped(|inner| inner.poll_close(cx))
+    }
+}
+
+impl<L, R> AsyncRead for SlogScope<L, R>
+where
+    R: AsyncRead,
+    L: Borrow<Logger>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_read(cx, buf))
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_read_vectored(cx, bufs))
+    }
+}
+
+impl<L, R> AsyncBufRead for SlogScope<L, R>
+where
+    R: AsyncBufRead,
+    L: Borrow<Logger>,
+{
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        self.scoped(|inner| inner.poll_fill_buf(cx))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.scoped(|inner| inner.consume(amt))
+    }
+}
+
+impl<L, W> AsyncWrite for SlogScope<L, W>
+where
+    W: AsyncWrite,
+    L: Borrow<Logger>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_write(cx, buf))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_write_vectored(cx, bufs))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.scoped(|inner| inner.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.scoped(|inner| inner.poll_close(cx))
+    }
+}
+
+/// Seeking runs inside the scope alongside any other I/O traits the wrapped
+/// object implements.
+///
+/// ```
+/// # use slog::o;
+/// # use std::{io::{self, SeekFrom}, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+/// use futures_util::io::{AsyncReadExt as _, AsyncSeekExt as _};
+/// use slog_scope_futures::futures03::AsyncReadExt;
+///
+/// /// A store recording the active scope whenever it's driven.
+/// struct Chunks(Arc<Mutex<Vec<String>>>);
+///
+/// impl Chunks {
+///     fn record(&self) {
+///         self.0.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+///     }
+/// }
+///
+/// impl futures_io::AsyncRead for Chunks {
+///     fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<io::Result<usize>> {
+///         self.record();
+///         Poll::Ready(Ok(0))
+///     }
+/// }
+///
+/// impl futures_io::AsyncSeek for Chunks {
+///     fn poll_seek(self: Pin<&mut Self>, _: &mut Context<'_>, pos: SeekFrom) -> Poll<io::Result<u64>> {
+///         self.record();
+///         match pos {
+///             SeekFrom::Start(pos) => Poll::Ready(Ok(pos)),
+///             _ => unimplemented!(),
+///         }
+///     }
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("chunk" => 1));
+/// let records = Arc::new(Mutex::new(Vec::new()));
+/// let mut store = Chunks(records.clone()).with_logger(logger);
+///
+/// futures_executor::block_on(async {
+///     assert_eq!(store.seek(SeekFrom::Start(42)).await.unwrap(), 42);
+///     store.read(&mut [0; 4]).await.unwrap();
+/// });
+///
+/// let records = records.lock().unwrap();
+/// assert_eq!(records.len(), 2);
+/// assert!(records.iter().all(|r| r == "Logger(chunk)"));
+/// ```
+impl<L, S> AsyncSeek for SlogScope<L, S>
+where
+    S: AsyncSeek,
+    L: Borrow<Logger>,
+{
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        self.scoped(|inner| inner.poll_seek(cx, pos))
+    }
+}
+
+/// Convenience trait for wrapping a `Stream` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `Stream`s.
+pub trait StreamExt: Stream + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+
+    /// Wrap `self` in a slog scope and box it
+    ///
+    /// The stream counterpart of
+    /// [`FutureExt::with_logger_boxed`](crate::std_future::FutureExt::with_logger_boxed):
+    /// the logger is cloned, so borrowed loggers work too.
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, BoxStream, StreamExt as _};
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// fn events(logger: &slog::Logger) -> BoxStream<'static, String> {
+    ///     stream::repeat_with(|| format!("{:?}", slog_scope::logger()))
+    ///         .take(2)
+    ///         .with_logger_boxed(logger)
+    /// }
+    ///
+    /// let conn = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let events = futures_executor::block_on(events(&conn).collect::<Vec<_>>());
+    /// assert_eq!(events, ["Logger(conn)"; 2]);
+    /// ```
+    fn with_logger_boxed<'a, L>(self, logger: L) -> BoxStream<'a, Self::Item>
+    where
+        Self: Send + 'a,
+        L: Borrow<Logger>,
+    {
+        Box::pin(SlogScope::new(logger.borrow().clone(), self))
+    }
+
+    /// Wrap `self` in a slog scope and box it, without requiring `Send`
+    fn with_logger_boxed_local<'a, L>(self, logger: L) -> LocalBoxStream<'a, Self::Item>
+    where
+        Self: 'a,
+        L: Borrow<Logger>,
+    {
+        Box::pin(SlogScope::new(logger.borrow().clone(), self))
+    }
+
+    /// Pair each item with a logger of its own, derived from the item by
+    /// `make_logger`
+    ///
+    /// The closure sees the item before it's yielded, and the resulting
+    /// [`ScopedItem`] runs its processing under that logger:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, StreamExt as _};
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// struct Job {
+    ///     id: u32,
+    /// }
+    ///
+    /// async fn process(job: Job) -> String {
+    ///     format!("{} {:?}", job.id, slog_scope::logger())
+    /// }
+    ///
+    /// let worker = slog::Logger::root(slog::Discard, o!("worker" => 1));
+    ///
+    /// let jobs = stream::iter(vec![Job { id: 1 }, Job { id: 2 }])
+    ///     .scope_items(|job| worker.new(o!("job_id" => job.id)))
+    ///     .then(|job| job.scope(process))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     futures_executor::block_on(jobs),
+    ///     ["1 Logger(job_id, worker)", "2 Logger(job_id, worker)"],
+    /// );
+    /// ```
+    fn scope_items<M>(self, make_logger: M) -> ScopeItems<Self, M>
+    where
+        M: FnMut(&Self::Item) -> Logger,
+    {
+        ScopeItems::new(self, make_logger)
+    }
+
+    /// Like `futures::StreamExt::for_each_concurrent`, but each item's future
+    /// runs under a logger of its own, derived from the item by
+    /// `make_logger`
+    ///
+    /// Futures are started and run concurrently exactly as upstream: at most
+    /// `limit` at a time, or without a bound if `limit` is `None` or zero.
+    /// The stream itself is polled in the caller's scope:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream;
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// let worker = slog::Logger::root(slog::Discard, o!("worker" => 1));
+    /// let seen = std::sync::Mutex::new(Vec::new());
+    /// let seen_by_jobs = &seen;
+    ///
+    /// let jobs = stream::iter(1..=3).for_each_concurrent_scoped(
+    ///     2,
+    ///     |job| worker.new(o!("job_id" => *job)),
+    ///     |job| async move {
+    ///         let scope = format!("{} {:?}", job, slog_scope::logger());
+    ///         seen_by_jobs.lock().unwrap().push(scope);
+    ///     },
+    /// );
+    /// futures_executor::block_on(jobs);
+    ///
+    /// assert_eq!(
+    ///     *seen.lock().unwrap(),
+    ///     ["1 Logger(job_id, worker)", "2 Logger(job_id, worker)", "3 Logger(job_id, worker)"],
+    /// );
+    /// ```
+    fn for_each_concurrent_scoped<M, F, Fut>(
+        self,
+        limit: impl Into<Option<usize>>,
+        make_logger: M,
+        f: F,
+    ) -> ForEachConcurrentScoped<Self, M, F, Fut>
+    where
+        M: FnMut(&Self::Item) -> Logger,
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        ForEachConcurrentScoped::new(self, limit.into(), make_logger, f)
+    }
+}
+
+impl<S> StreamExt for S where S: Stream {}
+
+pin_project! {
+    /// Stream for [`StreamExt::scope_items`].
+    pub struct ScopeItems<S, M> {
+        #[pin]
+        stream: S,
+        make_logger: M,
+    }
+}
+
+impl<S, M> ScopeItems<S, M> {
+    pub(crate) fn new(stream: S, make_logger: M) -> Self {
+        ScopeItems {
+            stream,
+            make_logger,
+        }
+    }
+}
+
+impl<S, M> Stream for ScopeItems<S, M>
+where
+    S: Stream,
+    M: FnMut(&S::Item) -> Logger,
+{
+    type Item = ScopedItem<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = ready!(this.stream.poll_next(cx));
+        let make_logger = this.make_logger;
+        Poll::Ready(item.map(|item| ScopedItem::new(make_logger(&item), item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+impl<S, M> FusedStream for ScopeItems<S, M>
+where
+    S: FusedStream,
+    M: FnMut(&S::Item) -> Logger,
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_terminated()
+    }
+}
+
+impl<L, S> SlogScope<L, S>
+where
+    L: Borrow<Logger>,
+    S: Stream,
+{
+    /// Number the stream's items, pairing each with a child of the scope's
+    /// logger carrying its index under `key`
+    ///
+    /// The stream itself is still polled in the scope of the wrapped logger.
+    /// The per-item scope covers whatever [`ScopedItem::scope`] builds from
+    /// the item, and ends when that future completes:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, StreamExt as _};
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// let batch = slog::Logger::root(slog::Discard, o!("batch" => 7));
+    ///
+    /// let rows = stream::iter(["a", "b"])
+    ///     .with_logger(batch)
+    ///     .with_item_index("item_index")
+    ///     .then(|row| row.scope(|row| async move { format!("{} {:?}", row, slog_scope::logger()) }))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(
+    ///     futures_executor::block_on(rows),
+    ///     ["a Logger(item_index, batch)", "b Logger(item_index, batch)"],
+    /// );
+    /// ```
+    pub fn with_item_index(self, key: &'static str) -> IndexedItems<L, S> {
+        IndexedItems::new(key, self)
+    }
+}
+
+pin_project! {
+    /// Stream for [`SlogScope::with_item_index`].
+    pub struct IndexedItems<L, S>
+    where
+        L: Borrow<Logger>,
+    {
+        key: &'static str,
+        next: u64,
+        #[pin]
+        scoped: SlogScope<L, S>,
+    }
+}
+
+impl<L, S> IndexedItems<L, S>
+where
+    L: Borrow<Logger>,
+{
+    pub(crate) fn new(key: &'static str, scoped: SlogScope<L, S>) -> Self {
+        IndexedItems {
+            key,
+            next: 0,
+            scoped,
+        }
+    }
+}
+
+impl<L, S> Stream for IndexedItems<L, S>
+where
+    L: Borrow<Logger>,
+    S: Stream,
+{
+    type Item = ScopedItem<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let item = match ready!(this.scoped.as_mut().poll_next(cx)) {
+            Some(item) => item,
+            None => return Poll::Ready(None),
+        };
+
+        let index = *this.next;
+        *this.next += 1;
+        let logger = this.scoped.logger().new(o!(*this.key => index));
+        Poll::Ready(Some(ScopedItem::new(logger, item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.scoped.size_hint()
+    }
+}
+
+impl<L, S> FusedStream for IndexedItems<L, S>
+where
+    L: Borrow<Logger>,
+    S: FusedStream,
+{
+    fn is_terminated(&self) -> bool {
+        self.scoped.is_terminated()
+    }
+}
+
+pin_project! {
+    /// Future for [`StreamExt::for_each_concurrent_scoped`].
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct ForEachConcurrentScoped<S, M, F, Fut> {
+        #[pin]
+        stream: Option<S>,
+        make_logger: M,
+        f: F,
+        futures: FuturesUnordered<SlogScope<Logger, Fut>>,
+        limit: Option<NonZeroUsize>,
+    }
+}
+
+impl<S, M, F, Fut> ForEachConcurrentScoped<S, M, F, Fut> {
+    pub(crate) fn new(stream: S, limit: Option<usize>, make_logger: M, f: F) -> Self {
+        ForEachConcurrentScoped {
+            stream: Some(stream),
+            make_logger,
+            f,
+            futures: FuturesUnordered::new(),
+            limit: limit.and_then(NonZeroUsize::new),
+        }
+    }
+}
+
+impl<S, M, F, Fut> Future for ForEachConcurrentScoped<S, M, F, Fut>
+where
+    S: Stream,
+    M: FnMut(&S::Item) -> Logger,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Output = ();
+
+    // Mirrors `futures_util::stream::ForEachConcurrent`, so that items are
+    // started and limited the same way.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut this = self.project();
+        loop {
+            let mut made_progress = false;
+
+            if this
+                .limit
+                .is_none_or(|limit| limit.get() > this.futures.len())
+            {
+                let item = match this.stream.as_mut().as_pin_mut().map(|s| s.poll_next(cx)) {
+                    Some(Poll::Ready(Some(item))) => Some(item),
+                    Some(Poll::Ready(None)) => {
+                        this.stream.set(None);
+                        None
+                    }
+                    Some(Poll::Pending) | None => None,
+                };
+                if let Some(item) = item {
+                    made_progress = true;
+                    let logger = (this.make_logger)(&item);
+                    this.futures.push(SlogScope::new(logger, (this.f)(item)));
+                }
+            }
+
+            match Pin::new(&mut *this.futures).poll_next(cx) {
+                Poll::Ready(Some(())) => made_progress = true,
+                Poll::Ready(None) if this.stream.is_none() => return Poll::Ready(()),
+                Poll::Ready(None) | Poll::Pending => {}
+            }
+
+            if !made_progress {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl<S, M, F, Fut> FusedFuture for ForEachConcurrentScoped<S, M, F, Fut>
+where
+    S: Stream,
+    M: FnMut(&S::Item) -> Logger,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_none() && self.futures.is_empty()
+    }
+}
+
+/// A stream item together with the logger its processing should run under.
+///
+/// Yielded by [`StreamExt::scope_items`] and [`SlogScope::with_item_index`].
+#[derive(Clone, Debug)]
+pub struct ScopedItem<T> {
+    logger: Logger,
+    item: T,
+}
+
+impl<T> ScopedItem<T> {
+    /// Pair `item` with `logger`
+    pub fn new(logger: Logger, item: T) -> Self {
+        ScopedItem { logger, item }
+    }
+
+    /// Get the item's logger
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Get a reference to the item
+    pub fn get_ref(&self) -> &T {
+        &self.item
+    }
+
+    /// Get a mutable reference to the item
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.item
+    }
+
+    /// Unwrap the item, dropping its logger
+    pub fn into_inner(self) -> T {
+        self.item
+    }
+
+    /// Split into the logger and the item
+    pub fn into_parts(self) -> (Logger, T) {
+        (self.logger, self.item)
+    }
+
+    /// Build a future from the item, running in the item's scope
+    pub fn scope<F, Fut>(self, f: F) -> SlogScope<Logger, Fut>
+    where
+        F: FnOnce(T) -> Fut,
+        Fut: Future,
+    {
+        SlogScope::new(self.logger, f(self.item))
+    }
+}
+
+/// Create a stream from a polling closure, called in a slog scope.
+///
+/// This is `futures::stream::poll_fn` with the scope entered around every
+/// call of `f`. The logger can be owned or borrowed:
+///
+/// ```
+/// # use slog::o;
+/// use std::task::Poll;
+/// use futures_util::stream::StreamExt as _;
+/// use slog_scope_futures::futures03::poll_fn_scoped;
+///
+/// let conn = slog::Logger::root(slog::Discard, o!("conn" => 1));
+///
+/// let mut polls = 0;
+/// let frames = poll_fn_scoped(conn, |cx| {
+///     polls += 1;
+///     if polls % 2 == 1 {
+///         cx.waker().wake_by_ref();
+///         return Poll::Pending;
+///     }
+///     let frame = (polls < 6).then(|| format!("{:?}", slog_scope::logger()));
+///     Poll::Ready(frame)
+/// });
+///
+/// let frames = futures_executor::block_on(frames.collect::<Vec<_>>());
+/// assert_eq!(frames, ["Logger(conn)"; 2]);
+/// ```
+pub fn poll_fn_scoped<L, T, F>(logger: L, f: F) -> SlogScope<L, stream::PollFn<F>>
+where
+    L: Borrow<Logger>,
+    F: FnMut(&mut Context<'_>) -> Poll<Option<T>>,
+{
+    SlogScope::new(logger, stream::poll_fn(f))
+}
+
+/// Like `futures::future::join_all`, but each future runs under a child of
+/// `logger` carrying its position in `iter` under an `index` KV
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::futures03::scoped_join_all;
+///
+/// let job = slog::Logger::root(slog::Discard, o!("job" => 1));
+///
+/// let shards = (0..2).map(|shard| async move { format!("{} {:?}", shard, slog_scope::logger()) });
+/// assert_eq!(
+///     futures_executor::block_on(scoped_join_all(job, shards)),
+///     ["0 Logger(index, job)", "1 Logger(index, job)"],
+/// );
+/// ```
+pub fn scoped_join_all<L, I>(logger: L, iter: I) -> future::JoinAll<SlogScope<Logger, I::Item>>
+where
+    L: Borrow<Logger>,
+    I: IntoIterator,
+    I::Item: Future,
+{
+    future::join_all(indexed(logger.borrow(), iter))
+}
+
+/// Like `futures::future::try_join_all`, but each future runs under a child
+/// of `logger` carrying its position in `iter` under an `index` KV
+///
+/// As upstream, the first error is returned right away and the other futures
+/// are dropped. They're dropped in their own scope, so whatever they log
+/// while being cancelled still carries their `index`:
+///
+/// ```
+/// # use slog::o;
+/// use futures_util::future;
+/// use slog_scope_futures::futures03::scoped_try_join_all;
+///
+/// let job = slog::Logger::root(slog::Discard, o!("job" => 1));
+///
+/// let shards = (0..3).map(|shard| async move {
+///     match shard {
+///         1 => Err(format!("shard failed under {:?}", slog_scope::logger())),
+///         2 => future::pending().await,
+///         _ => Ok(shard),
+///     }
+/// });
+/// assert_eq!(
+///     futures_executor::block_on(scoped_try_join_all(job, shards)),
+///     Err("shard failed under Logger(index, job)".to_owned()),
+/// );
+/// ```
+pub fn scoped_try_join_all<L, I, Fut, T, E>(
+    logger: L,
+    iter: I,
+) -> future::TryJoinAll<SlogScope<Logger, Fut>>
+where
+    L: Borrow<Logger>,
+    I: IntoIterator<Item = Fut>,
+    Fut: Future<Output = Result<T, E>>,
+{
+    future::try_join_all(indexed(logger.borrow(), iter))
+}
+
+fn indexed<I>(logger: &Logger, iter: I) -> impl Iterator<Item = SlogScope<Logger, I::Item>>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    let logger = logger.clone();
+    iter.into_iter()
+        .enumerate()
+        .map(move |(index, future)| SlogScope::new(logger.new(o!("index" => index)), future))
+}
+
+/// Convenience trait for wrapping a `Sink` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `Sink`s.
+pub trait SinkExt<Item>: Sink<Item> + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<S, Item> SinkExt<Item> for S where S: Sink<Item> {}
+
+/// Convenience trait for wrapping an `AsyncRead` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `AsyncRead`s. The resulting wrapper
+/// also implements any of the other I/O traits the reader does.
+///
+/// ```
+/// # use slog::o;
+/// # use std::{io, pin::Pin, task::{Context, Poll}};
+/// use futures_util::io::AsyncReadExt as _;
+/// use slog_scope_futures::futures03::AsyncReadExt;
+///
+/// /// A reader filling buffers with the active scope.
+/// struct ScopeReader;
+///
+/// impl futures_io::AsyncRead for ScopeReader {
+///     fn poll_read(
+///         self: Pin<&mut Self>,
+///         _: &mut Context<'_>,
+///         buf: &mut [u8],
+///     ) -> Poll<io::Result<usize>> {
+///         let scope = format!("{:?}", slog_scope::logger());
+///         buf[..scope.len()].copy_from_slice(scope.as_bytes());
+///         Poll::Ready(Ok(scope.len()))
+///     }
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+/// let mut reader = ScopeReader.with_logger(logger);
+///
+/// let mut buf = [0; 64];
+/// let n = futures_executor::block_on(reader.read(&mut buf)).unwrap();
+/// assert_eq!(&buf[..n], b"Logger(conn)");
+/// ```
+///
+/// Buffered readers keep their `AsyncBufRead` capabilities when wrapped, with
+/// both `poll_fill_buf` and `consume` running inside the scope:
+///
+/// ```
+/// # use slog::o;
+/// # use std::{io, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+/// use futures_util::io::AsyncBufReadExt as _;
+/// use slog_scope_futures::futures03::AsyncReadExt;
+///
+/// /// A buffered reader recording the active scope whenever it's driven.
+/// struct Lines {
+///     data: &'static [u8],
+///     records: Arc<Mutex<Vec<String>>>,
+/// }
+///
+/// impl Lines {
+///     fn record(&self) {
+///         self.records.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+///     }
+/// }
+///
+/// impl futures_io::AsyncRead for Lines {
+///     fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<io::Result<usize>> {
+///         unimplemented!()
+///     }
+/// }
+///
+/// impl futures_io::AsyncBufRead for Lines {
+///     fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+///         self.record();
+///         Poll::Ready(Ok(self.get_mut().data))
+///     }
+///
+///     fn consume(self: Pin<&mut Self>, amt: usize) {
+///         self.record();
+///         self.get_mut().data = &self.data[amt..];
+///     }
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+/// let records = Arc::new(Mutex::new(Vec::new()));
+/// let mut reader = Lines { data: b"hello\nworld\n", records: records.clone() }.with_logger(logger);
+///
+/// let mut line = String::new();
+/// futures_executor::block_on(reader.read_line(&mut line)).unwrap();
+/// assert_eq!(line, "hello\n");
+///
+/// let records = records.lock().unwrap();
+/// assert_eq!(records.len(), 2);
+/// assert!(records.iter().all(|r| r == "Logger(conn)"));
+/// ```
+pub trait AsyncReadExt: AsyncRead + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<R> AsyncReadExt for R where R: AsyncRead {}
+
+/// Convenience trait for wrapping an `AsyncWrite` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `AsyncWrite`s. As with
+/// [`AsyncReadExt`], a single wrapper covers objects that are both readable
+/// and writable.
+///
+/// ```
+/// # use slog::o;
+/// # use std::{io, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+/// use futures_util::io::{AsyncReadExt as _, AsyncWriteExt as _};
+/// use slog_scope_futures::futures03::AsyncWriteExt;
+///
+/// /// A connection recording the active scope whenever it's driven.
+/// struct Conn(Arc<Mutex<Vec<String>>>);
+///
+/// impl Conn {
+///     fn record(&self) {
+///         self.0.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+///     }
+/// }
+///
+/// impl futures_io::AsyncRead for Conn {
+///     fn poll_read(self: Pin<&mut Self>, _: &mut Context<'_>, _: &mut [u8]) -> Poll<io::Result<usize>> {
+///         self.record();
+///         Poll::Ready(Ok(0))
+///     }
+/// }
+///
+/// impl futures_io::AsyncWrite for Conn {
+///     fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+///         self.record();
+///         Poll::Ready(Ok(buf.len()))
+///     }
+///
+///     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+///         self.record();
+///         Poll::Ready(Ok(()))
+///     }
+///
+///     fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+///         self.record();
+///         Poll::Ready(Ok(()))
+///     }
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn_id" => 1));
+/// let records = Arc::new(Mutex::new(Vec::new()));
+/// let mut conn = Conn(records.clone()).with_logger(logger);
+///
+/// futures_executor::block_on(async {
+///     conn.write_all(b"ping").await.unwrap();
+///     conn.flush().await.unwrap();
+///     conn.read(&mut [0; 4]).await.unwrap();
+///     conn.close().await.unwrap();
+/// });
+///
+/// let records = records.lock().unwrap();
+/// assert_eq!(records.len(), 4);
+/// assert!(records.iter().all(|r| r == "Logger(conn_id)"));
+/// ```
+pub trait AsyncWriteExt: AsyncWrite + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+}
+
+impl<W> AsyncWriteExt for W where W: AsyncWrite {}
+
+/// Convenience trait for spawning futures in a slog scope.
+///
+/// Automatically implemented for all `Spawn`ers, such as
+/// `futures::executor::ThreadPool`.
+///
+/// ```
+/// # use slog::o;
+/// use std::sync::{mpsc, Arc, Barrier};
+/// use futures_executor::ThreadPool;
+/// use slog_scope_futures::futures03::SpawnExt;
+///
+/// let pool = ThreadPool::builder().pool_size(2).create().unwrap();
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// // Both tasks wait for each other, so they run on different workers.
+/// let barrier = Arc::new(Barrier::new(2));
+/// let (tx, rx) = mpsc::channel();
+///
+/// slog_scope::scope(&request, || {
+///     for _ in 0..2 {
+///         let (barrier, tx) = (barrier.clone(), tx.clone());
+///         pool.spawn_scoped(async move {
+///             barrier.wait();
+///             let thread = std::thread::current().id();
+///             tx.send((thread, format!("{:?}", slog_scope::logger()))).unwrap();
+///         })
+///         .unwrap();
+///     }
+/// });
+///
+/// let (a, b) = (rx.recv().unwrap(), rx.recv().unwrap());
+/// assert_ne!(a.0, b.0);
+/// assert_eq!([a.1, b.1], ["Logger(request_id)", "Logger(request_id)"]);
+/// ```
+pub trait SpawnExt: Spawn {
+    /// Spawn a task running in the current scope
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_scoped<F>(&self, future: F) -> Result<(), SpawnError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_scoped_with(slog_scope::logger(), future)
+    }
+
+    /// Spawn a task running in `logger`'s scope
+    fn spawn_scoped_with<F>(&self, logger: Logger, future: F) -> Result<(), SpawnError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_obj(FutureObj::new(Box::new(SlogScope::new(logger, future))))
+    }
+}
+
+impl<S> SpawnExt for S where S: Spawn + ?Sized {}
+
+/// A `Spawn`er running everything spawned through it in a slog scope.
+///
+/// This lets code spawning its own background tasks through a `Spawn` or
+/// `LocalSpawn` handle pick up a logger without knowing about it:
+///
+/// ```
+/// # use slog::o;
+/// # use std::sync::{Arc, Mutex};
+/// use futures_executor::LocalPool;
+/// use futures_task::{FutureObj, Spawn};
+/// use slog_scope_futures::futures03::ScopedSpawner;
+///
+/// /// Third-party code spawning background tasks.
+/// fn start_workers(spawner: &dyn Spawn, scopes: Arc<Mutex<Vec<String>>>) {
+///     for _ in 0..2 {
+///         let scopes = scopes.clone();
+///         let worker = async move { scopes.lock().unwrap().push(format!("{:?}", slog_scope::logger())) };
+///         spawner.spawn_obj(FutureObj::new(Box::new(worker))).unwrap();
+///     }
+/// }
+///
+/// let mut pool = LocalPool::new();
+/// let logger = slog::Logger::root(slog::Discard, o!("component" => "workers"));
+/// let scopes = Arc::new(Mutex::new(Vec::new()));
+///
+/// let spawner = ScopedSpawner::new(pool.spawner(), logger).with_spawn_id();
+/// start_workers(&spawner, scopes.clone());
+/// pool.run_until_stalled();
+///
+/// assert_eq!(*scopes.lock().unwrap(), ["Logger(spawn_id, component)"; 2]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedSpawner<S> {
+    spawner: S,
+    logger: Logger,
+    spawn_ids: Option<Arc<AtomicU64>>,
+}
+
+impl<S> ScopedSpawner<S> {
+    /// Wrap `spawner`, running spawned tasks in `logger`'s scope.
+    pub fn new(spawner: S, logger: Logger) -> Self {
+        ScopedSpawner {
+            spawner,
+            logger,
+            spawn_ids: None,
+        }
+    }
+
+    /// Also give each spawned task a `spawn_id` KV, counting up from 0.
+    ///
+    /// The count is shared between clones of the spawner.
+    pub fn with_spawn_id(self) -> Self {
+        ScopedSpawner {
+            spawn_ids: Some(Arc::new(AtomicU64::new(0))),
+            ..self
+        }
+    }
+
+    fn task_logger(&self) -> Logger {
+        match &self.spawn_ids {
+            Some(ids) => {
+                let id = ids.fetch_add(1, Ordering::Relaxed);
+                self.logger.new(o!("spawn_id" => id))
+            }
+            None => self.logger.clone(),
+        }
+    }
+}
+
+impl<S> Spawn for ScopedSpawner<S>
+where
+    S: Spawn,
+{
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        let future = SlogScope::new(self.task_logger(), future);
+
+        self.spawner.spawn_obj(FutureObj::new(Box::new(future)))
+    }
+
+    fn status(&self) -> Result<(), SpawnError> {
+        self.spawner.status()
+    }
+}
+
+impl<S> LocalSpawn for ScopedSpawner<S>
+where
+    S: LocalSpawn,
+{
+    fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
+        let future = SlogScope::new(self.task_logger(), future);
+
+        self.spawner
+            .spawn_local_obj(LocalFutureObj::new(Box::new(future)))
+    }
+
+    fn status_local(&self) -> Result<(), SpawnError> {
+        self.spawner.status_local()
+    }
+}
+
+/// A `FuturesUnordered` running each future pushed into it in a slog scope.
+///
+/// [`push_scoped`](Self::push_scoped) derives each future's logger from the
+/// set's own, with an `idx` KV counting up from 0, while
+/// [`push_with`](Self::push_with) takes the logger as is. Outputs are
+/// yielded in completion order, as with `FuturesUnordered`:
+///
+/// ```
+/// # use slog::o;
+/// use futures_util::stream::StreamExt as _;
+/// use slog_scope_futures::futures03::ScopedFuturesUnordered;
+///
+/// async fn sub_request() -> String {
+///     format!("{:?}", slog_scope::logger())
+/// }
+///
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+/// let mut requests = ScopedFuturesUnordered::new(request);
+///
+/// for _ in 0..2 {
+///     requests.push_scoped(sub_request());
+/// }
+/// let audit = slog::Logger::root(slog::Discard, o!("audit" => true));
+/// requests.push_with(audit, sub_request());
+///
+/// let mut scopes = futures_executor::block_on(requests.collect::<Vec<_>>());
+/// scopes.sort();
+/// assert_eq!(scopes, ["Logger(audit)", "Logger(idx, request_id)", "Logger(idx, request_id)"]);
+/// ```
+#[derive(Debug)]
+pub struct ScopedFuturesUnordered<F> {
+    logger: Logger,
+    next_idx: u64,
+    futures: FuturesUnordered<SlogScope<Logger, F>>,
+}
+
+impl<F> ScopedFuturesUnordered<F> {
+    /// Create an empty set, deriving the loggers of pushed futures from
+    /// `logger`.
+    pub fn new(logger: Logger) -> Self {
+        ScopedFuturesUnordered {
+            logger,
+            next_idx: 0,
+            futures: FuturesUnordered::new(),
+        }
+    }
+
+    /// Push a future running under a child of the set's logger, with the
+    /// next `idx`.
+    pub fn push_scoped(&mut self, future: F) {
+        let idx = self.next_idx;
+        self.next_idx += 1;
+
+        self.push_with(self.logger.new(o!("idx" => idx)), future);
+    }
+
+    /// Push a future running in `logger`'s scope.
+    pub fn push_with(&mut self, logger: Logger, future: F) {
+        self.futures.push(SlogScope::new(logger, future));
+    }
+
+    /// Get the logger pushed futures are derived from.
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Get the number of futures still running.
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    /// Check whether no futures are running.
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Unwrap the underlying `FuturesUnordered`.
+    pub fn into_inner(self) -> FuturesUnordered<SlogScope<Logger, F>> {
+        self.futures
+    }
+}
+
+impl<F> Stream for ScopedFuturesUnordered<F>
+where
+    F: Future,
+{
+    type Item = F::Output;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.futures).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.futures.size_hint()
+    }
+}
+
+impl<F> FusedStream for ScopedFuturesUnordered<F>
+where
+    F: Future,
+{
+    fn is_terminated(&self) -> bool {
+        self.futures.is_terminated()
+    }
+}
@@ -0,0 +1,57 @@
+//! Wrapping a response body keeps the request's scope active while the body
+//! is streamed, long after the handler future has completed:
+//!
+//! ```
+//! # use slog::o;
+//! use bytes::Bytes;
+//! use futures_util::stream::{self, StreamExt as _};
+//! use http_body::Frame;
+//! use http_body_util::{BodyExt, StreamBody};
+//! use slog_scope_futures::SlogScope;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("request_id" => 42));
+//!
+//! let chunks = stream::iter(0..3).map(|i| {
+//!     assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(request_id)");
+//!     Ok::<_, std::convert::Infallible>(Frame::data(Bytes::from(i.to_string())))
+//! });
+//! let body = SlogScope::new(logger, StreamBody::new(chunks));
+//!
+//! let body = futures_executor::block_on(BodyExt::collect(body)).unwrap().to_bytes();
+//! assert_eq!(body, "012");
+//! ```
+
+use std::{
+    borrow::Borrow,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http_body::{Body, Frame, SizeHint};
+use slog::Logger;
+
+use super::SlogScope;
+
+impl<L, B> Body for SlogScope<L, B>
+where
+    B: Body,
+    L: Borrow<Logger>,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.scoped(|inner| inner.poll_frame(cx))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.get_ref().is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.get_ref().size_hint()
+    }
+}
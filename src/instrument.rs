@@ -0,0 +1,1431 @@
+//! Opt-in diagnostics for scoped futures, logged through their own logger or
+//! read from a [`MetricsHandle`](crate::instrument::MetricsHandle):
+//!
+//! ```
+//! # use slog::o;
+//! use std::time::Duration;
+//! use slog::Level;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let handle = async { tokio::time::sleep(Duration::from_millis(1)).await };
+//!
+//! let (handle, metrics) = handle
+//!     .with_logger(logger)
+//!     .log_completion(Level::Info, "request finished")
+//!     .slow_poll_threshold(Duration::from_millis(50))
+//!     .instrumented();
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! runtime.block_on(handle);
+//! assert!(metrics.completed());
+//! ```
+//!
+//! These live behind a single boxed field on [`SlogScope`], so wrappers that
+//! don't enable any of them only pay for a null check per poll.
+
+use std::{
+    any::Any,
+    borrow::Borrow,
+    convert::TryFrom,
+    future::{Future, IntoFuture},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use atomic_waker::AtomicWaker;
+#[cfg(feature = "futures-03")]
+use futures_core::Stream;
+use slog::{debug, error, o, warn, FnValue, Level, Logger};
+
+use super::{SlogScope, TAKEN_APART};
+
+/// Log through `logger` at a level only known at runtime.
+///
+/// slog's own macros need the level to be a constant.
+macro_rules! log_at {
+    ($logger:expr, $level:expr, $($args:tt)+) => {
+        match $level {
+            slog::Level::Critical => slog::crit!($logger, $($args)+),
+            slog::Level::Error => slog::error!($logger, $($args)+),
+            slog::Level::Warning => slog::warn!($logger, $($args)+),
+            slog::Level::Info => slog::info!($logger, $($args)+),
+            slog::Level::Debug => slog::debug!($logger, $($args)+),
+            slog::Level::Trace => slog::trace!($logger, $($args)+),
+        }
+    };
+}
+
+pub(crate) use log_at;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Diagnostics {
+    log_panics: bool,
+    completion: Option<(Level, &'static str)>,
+    cancellation: Option<Cancellation>,
+    slow_poll: Option<Duration>,
+    schedule_delay: Option<ScheduleDelay>,
+    wake_latency: Option<Duration>,
+    wakes: Option<WakeTracking>,
+    wake_counts: Option<Level>,
+    task_id: Option<u64>,
+    summary: Option<(Level, &'static str)>,
+    poll_stats: Option<PollStats>,
+    // The thread of the last poll, once migrations are logged.
+    last_thread: Option<Option<Thread>>,
+    polls: u64,
+    items: u64,
+    metrics: Option<MetricsTracking>,
+    // Only set if a timing option is enabled.
+    first_poll: Option<Instant>,
+    completed: bool,
+}
+
+// Every wrapper carries these, so they must not take away its `Send` or
+// `Sync`: new state needs thread-safe types.
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    let _ = assert_send_sync::<Diagnostics>;
+};
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_task_id() -> u64 {
+    NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Cancellation {
+    level: Level,
+    msg: &'static str,
+    unpolled: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PollStats {
+    level: Level,
+    msg: &'static str,
+    busy: Duration,
+    max_poll: Duration,
+}
+
+impl PollStats {
+    fn new(level: Level, msg: &'static str) -> Self {
+        PollStats {
+            level,
+            msg,
+            busy: Duration::ZERO,
+            max_poll: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ScheduleDelay {
+    level: Level,
+    threshold: Duration,
+}
+
+/// When a wrapper was created, for
+/// [`log_schedule_delay`](SlogScope::log_schedule_delay).
+///
+/// Nothing is recorded on wasm, where `Instant::now` panics.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Created {
+    #[cfg(not(target_arch = "wasm32"))]
+    at: Instant,
+}
+
+impl Created {
+    pub(crate) fn now() -> Self {
+        Created {
+            #[cfg(not(target_arch = "wasm32"))]
+            at: Instant::now(),
+        }
+    }
+
+    fn elapsed(self) -> Option<Duration> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(self.at.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        return None;
+    }
+}
+
+impl Diagnostics {
+    fn is_timed(&self) -> bool {
+        self.completion.is_some()
+            || self.cancellation.is_some()
+            || self.summary.is_some()
+            || self.poll_stats.is_some()
+            || self.metrics.is_some()
+    }
+
+    /// Run `poll_inner` with the enabled diagnostics, `is_done` telling
+    /// whether its result completes the future or stream.
+    fn poll<T>(
+        &mut self,
+        logger: &Logger,
+        created: Created,
+        cx: &mut Context<'_>,
+        poll_inner: impl FnOnce(&mut Context<'_>) -> Poll<T>,
+        is_done: fn(&Poll<T>) -> bool,
+    ) -> Poll<T> {
+        if self.first_poll.is_none() && self.is_timed() {
+            self.first_poll = Some(Instant::now());
+        }
+
+        if let Some(delay) = self.schedule_delay.take() {
+            match created.elapsed() {
+                Some(elapsed) if elapsed >= delay.threshold => {
+                    log_at!(logger, delay.level, "first poll"; "schedule_delay_ms" => elapsed.as_millis());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(last) = &mut self.last_thread {
+            let current = thread::current();
+            match last {
+                Some(prev) if prev.id() != current.id() => {
+                    debug!(logger, "thread migration"; "to_thread" => describe(&current), "from_thread" => describe(prev));
+                }
+                _ => {}
+            }
+            *last = Some(current);
+        }
+
+        let times_polls =
+            self.slow_poll.is_some() || self.poll_stats.is_some() || self.metrics.is_some();
+        let poll_start = times_polls.then(Instant::now);
+        let poll = match &self.wakes {
+            Some(wakes) => {
+                let latency = wakes.0.start_poll(cx.waker());
+                if let (Some(latency), Some(threshold)) = (latency, self.wake_latency) {
+                    if latency > threshold {
+                        warn!(logger, "slow wake-up"; "wake_to_poll_ms" => latency.as_millis());
+                    }
+                }
+
+                let waker = Waker::from(Arc::clone(&wakes.0));
+                let mut cx = Context::from_waker(&waker);
+                poll_catching(self.log_panics, logger, || poll_inner(&mut cx))
+            }
+            None => poll_catching(self.log_panics, logger, || poll_inner(cx)),
+        };
+        self.polls += 1;
+
+        if let Some(start) = poll_start {
+            let duration = start.elapsed();
+            if let Some(stats) = &mut self.poll_stats {
+                stats.busy += duration;
+                stats.max_poll = stats.max_poll.max(duration);
+            }
+            if let Some(MetricsTracking(metrics)) = &self.metrics {
+                metrics.record_poll(duration);
+                if let Some(wakes) = &self.wakes {
+                    metrics.record_wakes(wakes.0.wakes.load(Ordering::Relaxed));
+                }
+            }
+            if self.slow_poll.is_some_and(|threshold| duration > threshold) {
+                warn!(logger, "slow poll"; "poll" => self.polls, "poll_duration_ms" => duration.as_millis());
+            }
+        }
+
+        if is_done(&poll) && !self.completed {
+            self.completed = true;
+            if let (Some(MetricsTracking(metrics)), Some(start)) = (&self.metrics, self.first_poll)
+            {
+                metrics.record_completion(start.elapsed());
+            }
+            self.on_completion(logger);
+        }
+
+        poll
+    }
+
+    fn on_completion(&self, logger: &Logger) {
+        if let Some((level, msg)) = self.completion {
+            log_at!(logger, level, "{}", msg; "elapsed_ms" => self.elapsed_ms());
+        }
+
+        self.log_summary(logger, true);
+
+        if let (Some(stats), Some(start)) = (&self.poll_stats, self.first_poll) {
+            let total = start.elapsed();
+            log_at!(logger, stats.level, "{}", stats.msg;
+                "total_ms" => total.as_millis(),
+                "max_poll_us" => stats.max_poll.as_micros(),
+                "idle_us" => total.saturating_sub(stats.busy).as_micros(),
+                "busy_us" => stats.busy.as_micros(),
+                "polls" => self.polls,
+            );
+        }
+
+        if let (Some(level), Some(wakes)) = (self.wake_counts, &self.wakes) {
+            let wakes = wakes.0.wakes.load(Ordering::Relaxed);
+            let wakes_per_poll = wakes as f64 / self.polls as f64;
+            log_at!(logger, level, "wake counts"; "wakes_per_poll" => wakes_per_poll, "polls" => self.polls, "wakes" => wakes);
+        }
+    }
+
+    pub(crate) fn on_drop(&self, logger: &Logger) {
+        let polled = self.first_poll.is_some();
+
+        match self.cancellation {
+            Some(c) if !self.completed && (polled || c.unpolled) => {
+                log_at!(logger, c.level, "{}", c.msg; "polled" => polled, "elapsed_ms" => self.elapsed_ms());
+            }
+            _ => {}
+        }
+
+        if !self.completed {
+            self.log_summary(logger, false);
+        }
+    }
+
+    fn log_summary(&self, logger: &Logger, completed: bool) {
+        if let Some((level, msg)) = self.summary {
+            log_at!(logger, level, "{}", msg; "completed" => completed, "elapsed_ms" => self.elapsed_ms(), "items" => self.items);
+        }
+    }
+
+    fn elapsed_ms(&self) -> u128 {
+        self.first_poll
+            .map_or(0, |start| start.elapsed().as_millis())
+    }
+}
+
+/// A thread's name, if it has one, and id, e.g. `worker-1 (ThreadId(3))`.
+fn describe(thread: &Thread) -> String {
+    format!(
+        "{} ({:?})",
+        thread.name().unwrap_or("<unnamed>"),
+        thread.id()
+    )
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    polls: AtomicU64,
+    busy_nanos: AtomicU64,
+    total_nanos: AtomicU64,
+    wakes: AtomicU64,
+    counts_wakes: AtomicBool,
+    completed: AtomicBool,
+}
+
+impl Metrics {
+    fn record_poll(&self, duration: Duration) {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.busy_nanos
+            .fetch_add(nanos(duration), Ordering::Relaxed);
+    }
+
+    fn record_wakes(&self, wakes: u64) {
+        self.wakes.store(wakes, Ordering::Relaxed);
+        self.counts_wakes.store(true, Ordering::Relaxed);
+    }
+
+    fn record_completion(&self, total: Duration) {
+        self.total_nanos.store(nanos(total), Ordering::Relaxed);
+        self.completed.store(true, Ordering::Release);
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// The waker handed to the inner future, timestamping wake-ups before
+/// forwarding them to the task's own waker.
+///
+/// There's one per wrapper. The task's waker is registered in an
+/// `AtomicWaker` on every poll, so forwarding a wake-up never takes a lock.
+#[derive(Debug)]
+struct WakeTracker {
+    waker: AtomicWaker,
+    epoch: Instant,
+    // Nanoseconds since `epoch` of the first wake-up since the last poll, or
+    // 0 if there has been none.
+    woken_at: AtomicU64,
+    wakes: AtomicU64,
+}
+
+impl WakeTracker {
+    /// Register the task's waker for this poll, returning the time since its
+    /// last wake-up, if any.
+    fn start_poll(&self, waker: &Waker) -> Option<Duration> {
+        self.waker.register(waker);
+
+        match self.woken_at.swap(0, Ordering::Relaxed) {
+            0 => None,
+            woken_at => Some(
+                self.epoch
+                    .elapsed()
+                    .saturating_sub(Duration::from_nanos(woken_at)),
+            ),
+        }
+    }
+}
+
+impl Default for WakeTracker {
+    fn default() -> Self {
+        WakeTracker {
+            waker: AtomicWaker::new(),
+            epoch: Instant::now(),
+            woken_at: AtomicU64::new(0),
+            wakes: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Wake for WakeTracker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.wakes.fetch_add(1, Ordering::Relaxed);
+        let now = nanos(self.epoch.elapsed()).max(1);
+        let _ = self
+            .woken_at
+            .compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+        self.waker.wake();
+    }
+}
+
+/// A [`WakeTracker`] that isn't shared between clones of a wrapper, since
+/// they're polled by different tasks.
+#[derive(Debug, Default)]
+struct WakeTracking(Arc<WakeTracker>);
+
+impl Clone for WakeTracking {
+    fn clone(&self) -> Self {
+        WakeTracking::default()
+    }
+}
+
+/// [`Metrics`] that aren't shared between clones of a wrapper, since they're
+/// polled by different tasks: a clone starts over, leaving the original's
+/// [`MetricsHandle`] alone.
+#[derive(Debug, Default)]
+struct MetricsTracking(Arc<Metrics>);
+
+impl Clone for MetricsTracking {
+    fn clone(&self) -> Self {
+        MetricsTracking::default()
+    }
+}
+
+/// A reader for the poll metrics of a scoped future.
+///
+/// Created by [`SlogScope::instrumented`]. It can be cloned and read from any
+/// thread while the future runs; updates are atomic, and never lock.
+#[derive(Clone, Debug)]
+pub struct MetricsHandle {
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsHandle {
+    /// The number of times the future has been polled.
+    pub fn polls(&self) -> u64 {
+        self.metrics.polls.load(Ordering::Relaxed)
+    }
+
+    /// The total time spent polling the future.
+    pub fn busy_time(&self) -> Duration {
+        Duration::from_nanos(self.metrics.busy_nanos.load(Ordering::Relaxed))
+    }
+
+    /// The number of times the inner future's waker was used, as of its
+    /// latest poll.
+    ///
+    /// This is only counted if wake-ups are tracked, e.g. with
+    /// [`SlogScope::log_wake_counts`].
+    pub fn wakes(&self) -> Option<u64> {
+        if self.metrics.counts_wakes.load(Ordering::Relaxed) {
+            Some(self.metrics.wakes.load(Ordering::Relaxed))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the future has completed.
+    pub fn completed(&self) -> bool {
+        self.metrics.completed.load(Ordering::Acquire)
+    }
+
+    /// The time from first poll to completion, if the future has completed.
+    pub fn time_to_completion(&self) -> Option<Duration> {
+        if self.completed() {
+            Some(Duration::from_nanos(
+                self.metrics.total_nanos.load(Ordering::Relaxed),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+fn poll_catching<T>(log_panics: bool, logger: &Logger, poll: impl FnOnce() -> Poll<T>) -> Poll<T> {
+    if !log_panics {
+        return poll();
+    }
+
+    // The panic is resumed right away and nothing is touched in between, so
+    // there's no broken state to observe.
+    match panic::catch_unwind(AssertUnwindSafe(poll)) {
+        Ok(poll) => poll,
+        Err(payload) => {
+            error!(logger, "future panicked"; "panic" => panic_message(&*payload));
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+impl<L, F> SlogScope<L, F>
+where
+    L: Borrow<Logger>,
+{
+    /// Log panics from the inner future through the scoped logger.
+    ///
+    /// A panic while polling is logged as an error with the panic message
+    /// under a `panic` KV, then resumed, so whatever would've caught it still
+    /// does:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("task" => "flaky"));
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let out = runtime.block_on(async {
+    ///     tokio::spawn(async { panic!("oh no") }.with_logger(logger).log_panics()).await
+    /// });
+    ///
+    /// assert!(out.unwrap_err().is_panic());
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Error);
+    /// assert_eq!(records[0].msg(), "future panicked");
+    /// assert_eq!(records[0].kv("panic"), Some("oh no"));
+    /// assert_eq!(records[0].kv("task"), Some("flaky"));
+    /// ```
+    ///
+    /// This only applies when the wrapper is polled as a `Future` or a
+    /// `Stream`, as do the other diagnostics.
+    pub fn log_panics(mut self) -> Self {
+        self.diagnostics_mut().log_panics = true;
+        self
+    }
+
+    /// Log a record through the scoped logger once the inner future
+    /// completes.
+    ///
+    /// The record carries an `elapsed_ms` KV, counted from the first poll.
+    /// Nothing is logged if the future is dropped before completing:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::time::Duration;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    ///
+    /// let handle = async {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    /// };
+    /// let unfinished = std::future::pending::<()>();
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    /// runtime.block_on(async {
+    ///     let handle = handle.with_logger(&logger).log_completion(Level::Info, "request finished");
+    ///     let unfinished = unfinished.with_logger(&logger).log_completion(Level::Info, "never");
+    ///     tokio::select! {
+    ///         _ = handle => {}
+    ///         _ = unfinished => {}
+    ///     }
+    /// });
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Info);
+    /// assert_eq!(records[0].msg(), "request finished");
+    /// assert_eq!(records[0].kv("request_id"), Some("1"));
+    ///
+    /// let elapsed: u64 = records[0].kv("elapsed_ms").unwrap().parse().unwrap();
+    /// assert!((20..1000).contains(&elapsed));
+    /// ```
+    pub fn log_completion(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().completion = Some((level, msg));
+        self
+    }
+
+    /// Log a record through the scoped logger if the wrapper is dropped
+    /// before the inner future completes.
+    ///
+    /// The record carries a `polled` KV telling whether the future was ever
+    /// polled, and an `elapsed_ms` KV counted from the first poll. Futures
+    /// that ran to completion are never logged:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{future, task::Context};
+    /// use futures_util::{task::noop_waker, FutureExt as _};
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("op" => "sync"));
+    /// let waker = noop_waker();
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// let unpolled = future::pending::<()>().with_logger(&logger);
+    /// drop(unpolled.log_cancellation(Level::Warning, "sync cancelled"));
+    ///
+    /// let mut in_flight = future::pending::<()>()
+    ///     .with_logger(&logger)
+    ///     .log_cancellation(Level::Warning, "sync cancelled");
+    /// assert!(in_flight.poll_unpin(&mut cx).is_pending());
+    /// drop(in_flight);
+    ///
+    /// let mut done = future::ready(())
+    ///     .with_logger(&logger)
+    ///     .log_cancellation(Level::Warning, "sync cancelled");
+    /// assert!(done.poll_unpin(&mut cx).is_ready());
+    /// drop(done);
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 2);
+    /// for (record, polled) in records.iter().zip(["false", "true"]) {
+    ///     assert_eq!(record.level(), Level::Warning);
+    ///     assert_eq!(record.msg(), "sync cancelled");
+    ///     assert_eq!(record.kv("polled"), Some(polled));
+    ///     assert_eq!(record.kv("elapsed_ms"), Some("0"));
+    ///     assert_eq!(record.kv("op"), Some("sync"));
+    /// }
+    /// ```
+    pub fn log_cancellation(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().cancellation = Some(Cancellation {
+            level,
+            msg,
+            unpolled: true,
+        });
+        self
+    }
+
+    /// Like [`log_cancellation`](Self::log_cancellation), but doesn't log
+    /// wrappers dropped without ever being polled.
+    ///
+    /// ```
+    /// use std::{future, task::Context};
+    /// use futures_util::{task::noop_waker, FutureExt as _};
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    ///
+    /// let unpolled = future::pending::<()>().with_logger(&logger);
+    /// drop(unpolled.log_cancellation_if_polled(Level::Warning, "cancelled"));
+    /// assert!(records.is_empty());
+    ///
+    /// let mut in_flight = future::pending::<()>()
+    ///     .with_logger(&logger)
+    ///     .log_cancellation_if_polled(Level::Warning, "cancelled");
+    /// let waker = noop_waker();
+    /// assert!(in_flight.poll_unpin(&mut Context::from_waker(&waker)).is_pending());
+    /// drop(in_flight);
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].msg(), "cancelled");
+    /// assert_eq!(records[0].kv("polled"), Some("true"));
+    /// ```
+    pub fn log_cancellation_if_polled(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().cancellation = Some(Cancellation {
+            level,
+            msg,
+            unpolled: false,
+        });
+        self
+    }
+
+    /// Log a warning through the scoped logger for every poll of the inner
+    /// future taking longer than `threshold`.
+    ///
+    /// This is meant to catch blocking work done on the executor. The record
+    /// carries the poll's 1-based index under `poll`, and its duration under
+    /// `poll_duration_ms`:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{task::Poll, thread, time::Duration};
+    /// use futures_util::future;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("task" => "index"));
+    ///
+    /// let mut polls = 0;
+    /// let index = future::poll_fn(|cx| {
+    ///     polls += 1;
+    ///     if polls == 2 {
+    ///         // Oops, blocking.
+    ///         thread::sleep(Duration::from_millis(50));
+    ///     }
+    ///     if polls < 4 {
+    ///         cx.waker().wake_by_ref();
+    ///         Poll::Pending
+    ///     } else {
+    ///         Poll::Ready(())
+    ///     }
+    /// });
+    ///
+    /// let index = index.with_logger(logger).slow_poll_threshold(Duration::from_millis(20));
+    /// futures_executor::block_on(index);
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Warning);
+    /// assert_eq!(records[0].msg(), "slow poll");
+    /// assert_eq!(records[0].kv("poll"), Some("2"));
+    /// assert_eq!(records[0].kv("task"), Some("index"));
+    ///
+    /// let duration: u64 = records[0].kv("poll_duration_ms").unwrap().parse().unwrap();
+    /// assert!(duration >= 20);
+    /// ```
+    pub fn slow_poll_threshold(mut self, threshold: Duration) -> Self {
+        self.diagnostics_mut().slow_poll = Some(threshold);
+        self
+    }
+
+    /// Record poll metrics of the inner future, and get a handle to read
+    /// them.
+    ///
+    /// Unlike the other diagnostics, nothing is logged. A clone of the
+    /// wrapper records metrics of its own, which the handle doesn't see:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{task::{Context, Poll}, time::Duration};
+    /// use futures_util::{future, task::noop_waker, FutureExt as _};
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!());
+    /// let mut polls = 0;
+    /// let fut = future::poll_fn(|_| {
+    ///     polls += 1;
+    ///     std::thread::sleep(Duration::from_millis(5));
+    ///     if polls < 3 { Poll::Pending } else { Poll::Ready(()) }
+    /// });
+    /// let (mut fut, metrics) = fut.with_logger(logger).instrumented();
+    ///
+    /// let waker = noop_waker();
+    /// let mut cx = Context::from_waker(&waker);
+    /// assert!(fut.poll_unpin(&mut cx).is_pending());
+    /// assert!(fut.poll_unpin(&mut cx).is_pending());
+    /// assert_eq!((metrics.polls(), metrics.completed()), (2, false));
+    /// assert_eq!(metrics.time_to_completion(), None);
+    ///
+    /// assert!(fut.poll_unpin(&mut cx).is_ready());
+    /// assert_eq!((metrics.polls(), metrics.completed()), (3, true));
+    /// assert!(metrics.busy_time() >= Duration::from_millis(15));
+    /// assert!(metrics.time_to_completion().unwrap() >= metrics.busy_time());
+    /// ```
+    pub fn instrumented(mut self) -> (Self, MetricsHandle) {
+        let metrics = self
+            .diagnostics_mut()
+            .metrics
+            .get_or_insert_with(Default::default)
+            .0
+            .clone();
+
+        (self, MetricsHandle { metrics })
+    }
+
+    /// Log a record through the scoped logger on first poll, with the time
+    /// since the wrapper was created under `schedule_delay_ms`.
+    ///
+    /// Wrapping the future right before spawning measures how long it waited
+    /// in the executor's queue. Delays shorter than `threshold` aren't
+    /// logged:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{thread, time::Duration};
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("job" => 1));
+    /// let threshold = Duration::from_millis(10);
+    ///
+    /// let fast = async {}.with_logger(&logger).log_schedule_delay(Level::Debug, threshold);
+    /// futures_executor::block_on(fast);
+    /// assert!(records.is_empty());
+    ///
+    /// let queued = async {}.with_logger(&logger);
+    /// thread::sleep(Duration::from_millis(30));
+    /// futures_executor::block_on(queued.log_schedule_delay(Level::Debug, threshold));
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Debug);
+    /// assert_eq!(records[0].msg(), "first poll");
+    /// assert_eq!(records[0].kv("job"), Some("1"));
+    ///
+    /// let delay: u64 = records[0].kv("schedule_delay_ms").unwrap().parse().unwrap();
+    /// assert!((30..1000).contains(&delay));
+    /// ```
+    pub fn log_schedule_delay(mut self, level: Level, threshold: Duration) -> Self {
+        self.diagnostics_mut().schedule_delay = Some(ScheduleDelay { level, threshold });
+        self
+    }
+
+    /// Log a warning through the scoped logger whenever the inner future is
+    /// polled more than `threshold` after being woken.
+    ///
+    /// This catches tasks starved by a busy executor, which slow polls
+    /// wouldn't show. The inner future is handed a waker recording when it's
+    /// woken, and forwarding to the task's own waker. The record carries the
+    /// latency under `wake_to_poll_ms`:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{
+    ///     sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex},
+    ///     task::{Context, Poll, Waker},
+    ///     thread,
+    ///     time::Duration,
+    /// };
+    /// use futures_util::{future, task::{waker, ArcWake}, FutureExt as _};
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// /// The task's waker, counting wake-ups.
+    /// #[derive(Default)]
+    /// struct Task(AtomicUsize);
+    ///
+    /// impl ArcWake for Task {
+    ///     fn wake_by_ref(arc_self: &Arc<Self>) {
+    ///         arc_self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("conn" => 1));
+    ///
+    /// let stored = Mutex::new(None::<Waker>);
+    /// let mut conn = future::poll_fn(|cx| {
+    ///     *stored.lock().unwrap() = Some(cx.waker().clone());
+    ///     Poll::<()>::Pending
+    /// })
+    /// .with_logger(logger)
+    /// .wake_latency_threshold(Duration::from_millis(20));
+    ///
+    /// let task = Arc::new(Task::default());
+    /// let task_waker = waker(task.clone());
+    /// let mut cx = Context::from_waker(&task_waker);
+    /// let wake = || stored.lock().unwrap().take().unwrap().wake();
+    ///
+    /// // Polled right after waking up.
+    /// assert!(conn.poll_unpin(&mut cx).is_pending());
+    /// wake();
+    /// assert!(conn.poll_unpin(&mut cx).is_pending());
+    /// assert!(records.is_empty());
+    ///
+    /// // Polled late.
+    /// wake();
+    /// thread::sleep(Duration::from_millis(40));
+    /// assert!(conn.poll_unpin(&mut cx).is_pending());
+    ///
+    /// // Wake-ups reached the task's waker either way.
+    /// assert_eq!(task.0.load(Ordering::SeqCst), 2);
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Warning);
+    /// assert_eq!(records[0].msg(), "slow wake-up");
+    /// assert_eq!(records[0].kv("conn"), Some("1"));
+    ///
+    /// let latency: u64 = records[0].kv("wake_to_poll_ms").unwrap().parse().unwrap();
+    /// assert!((40..1000).contains(&latency));
+    /// ```
+    pub fn wake_latency_threshold(mut self, threshold: Duration) -> Self {
+        let diagnostics = self.diagnostics_mut();
+        diagnostics.wake_latency = Some(threshold);
+        diagnostics.wakes.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// Count wake-ups of the inner future, and log them through the scoped
+    /// logger on completion.
+    ///
+    /// The record carries `wakes`, `polls` and `wakes_per_poll` KVs. Lots of
+    /// wake-ups per poll point at something busy-looping. For streams, it's
+    /// logged when the stream ends:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::task::Poll;
+    /// use futures_util::{future, stream::{self, StreamExt as _}};
+    /// use slog::Level;
+    /// use slog_scope_futures::{futures03::StreamExt, test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("conn" => 1));
+    ///
+    /// // Woken three times on every poll.
+    /// let mut polls = 0;
+    /// let spurious = future::poll_fn(|cx| {
+    ///     polls += 1;
+    ///     for _ in 0..3 {
+    ///         cx.waker().wake_by_ref();
+    ///     }
+    ///     if polls < 4 { Poll::Pending } else { Poll::Ready(()) }
+    /// });
+    ///
+    /// let (spurious, metrics) = spurious
+    ///     .with_logger(&logger)
+    ///     .log_wake_counts(Level::Info)
+    ///     .instrumented();
+    /// futures_executor::block_on(spurious);
+    /// assert_eq!(metrics.wakes(), Some(12));
+    ///
+    /// let items = stream::iter(1..=3).with_logger(&logger).log_wake_counts(Level::Info);
+    /// assert_eq!(futures_executor::block_on(items.collect::<Vec<_>>()), [1, 2, 3]);
+    ///
+    /// let records = records.records();
+    /// assert!(records.iter().all(|r| r.msg() == "wake counts" && r.kv("conn") == Some("1")));
+    ///
+    /// let counts: Vec<_> = records
+    ///     .iter()
+    ///     .map(|r| (r.kv("wakes"), r.kv("polls"), r.kv("wakes_per_poll")))
+    ///     .collect();
+    /// assert_eq!(
+    ///     counts,
+    ///     [
+    ///         (Some("12"), Some("4"), Some("3")),
+    ///         (Some("0"), Some("4"), Some("0")),
+    ///     ],
+    /// );
+    /// ```
+    pub fn log_wake_counts(mut self, level: Level) -> Self {
+        let diagnostics = self.diagnostics_mut();
+        diagnostics.wake_counts = Some(level);
+        diagnostics.wakes.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// Give the wrapper a unique id, and add it to the logger under a `task`
+    /// KV.
+    ///
+    /// The child logger is built right away, rather than on every poll, and
+    /// the id can be read back with [`task_id`](Self::task_id), e.g. to match
+    /// log lines with a join handle:
+    ///
+    /// ```
+    /// # use slog::{info, o};
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    ///
+    /// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+    /// let tasks: Vec<_> = (0..2)
+    ///     .map(|_| {
+    ///         let task = async {
+    ///             info!(slog_scope::logger(), "started");
+    ///             tokio::task::yield_now().await;
+    ///             info!(slog_scope::logger(), "done");
+    ///         };
+    ///         let task = task.with_logger(&logger).tag_task();
+    ///         (task.task_id().unwrap(), runtime.spawn(task))
+    ///     })
+    ///     .collect();
+    ///
+    /// assert!(tasks[0].0 < tasks[1].0);
+    ///
+    /// for (id, handle) in tasks {
+    ///     runtime.block_on(handle).unwrap();
+    ///
+    ///     let id = id.to_string();
+    ///     let msgs: Vec<_> = records
+    ///         .records()
+    ///         .into_iter()
+    ///         .filter(|r| r.kv("task") == Some(&id))
+    ///         .inspect(|r| assert_eq!(r.kv("request_id"), Some("1")))
+    ///         .map(|r| r.msg().to_owned())
+    ///         .collect();
+    ///     assert_eq!(msgs, ["started", "done"]);
+    /// }
+    /// ```
+    pub fn tag_task(mut self) -> SlogScope<Logger, F> {
+        let id = next_task_id();
+        self.diagnostics_mut().task_id = Some(id);
+
+        self.map_logger(|logger| logger.borrow().new(o!("task" => id)))
+    }
+
+    /// Log a debug record through the scoped logger whenever the wrapper is
+    /// polled on another thread than the time before.
+    ///
+    /// The record carries `from_thread` and `to_thread` KVs, each holding the
+    /// thread's name and id. This helps tell when a task moves between the
+    /// workers of a multi-threaded runtime:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{future::Future, pin::pin, task::{Context, Poll}, thread};
+    /// use futures_util::task::noop_waker_ref;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    ///
+    /// let mut polls = 0;
+    /// let request = std::future::poll_fn(|_| {
+    ///     polls += 1;
+    ///     if polls < 3 { Poll::Pending } else { Poll::Ready(()) }
+    /// });
+    /// let mut request = pin!(request.with_logger(&logger).log_thread_migrations());
+    ///
+    /// let mut poll = || request.as_mut().poll(&mut Context::from_waker(noop_waker_ref()));
+    /// assert!(poll().is_pending());
+    /// assert!(poll().is_pending());
+    /// thread::scope(|s| {
+    ///     let worker = thread::Builder::new().name("worker".into());
+    ///     worker.spawn_scoped(s, || assert!(poll().is_ready())).unwrap();
+    /// });
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].msg(), "thread migration");
+    /// assert!(records[0].kv("from_thread").unwrap().starts_with("main ("));
+    /// assert!(records[0].kv("to_thread").unwrap().starts_with("worker ("));
+    /// ```
+    pub fn log_thread_migrations(mut self) -> Self {
+        self.diagnostics_mut().last_thread.get_or_insert(None);
+        self
+    }
+
+    /// Add the thread emitting each record to the logger under a `thread`
+    /// KV.
+    ///
+    /// Like [`tag_task`](Self::tag_task), the child logger is built right
+    /// away, but its `thread` KV is only evaluated when a record is logged,
+    /// so it shows the thread the wrapper is polled on at the time:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog::info;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    ///
+    /// let runtime = tokio::runtime::Builder::new_multi_thread()
+    ///     .thread_name("worker")
+    ///     .build()
+    ///     .unwrap();
+    /// let task = async { info!(slog_scope::logger(), "started") };
+    /// runtime.block_on(runtime.spawn(task.with_logger(&logger).tag_thread())).unwrap();
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records[0].msg(), "started");
+    /// assert!(records[0].kv("thread").unwrap().starts_with("worker ("));
+    /// assert_eq!(records[0].kv("request_id"), Some("1"));
+    /// ```
+    pub fn tag_thread(self) -> SlogScope<Logger, F> {
+        self.map_logger(|logger| {
+            let thread = FnValue(|_| describe(&thread::current()));
+            logger.borrow().new(o!("thread" => thread))
+        })
+    }
+
+    /// Log a summary of the inner stream through the scoped logger when it
+    /// ends, or when the wrapper is dropped before it does.
+    ///
+    /// The record carries an `items` KV counting the items yielded, an
+    /// `elapsed_ms` KV counted from the first poll, and a `completed` KV
+    /// telling the two cases apart. It's only ever logged once, even if the
+    /// stream is polled again after it ended:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, StreamExt as _};
+    /// use slog::Level;
+    /// use slog_scope_futures::{futures03::StreamExt, test_util::capture};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("consumer" => 1));
+    ///
+    /// let mut jobs = stream::iter(0..3)
+    ///     .fuse()
+    ///     .with_logger(&logger)
+    ///     .log_summary(Level::Info, "consumer finished");
+    /// let mut events = stream::iter(0..3)
+    ///     .with_logger(&logger)
+    ///     .log_summary(Level::Info, "consumer stopped");
+    ///
+    /// futures_executor::block_on(async {
+    ///     while jobs.next().await.is_some() {}
+    ///     assert_eq!(jobs.next().await, None);
+    ///     events.next().await;
+    /// });
+    /// drop(events);
+    ///
+    /// let records = records.records();
+    /// let summaries: Vec<_> = records
+    ///     .iter()
+    ///     .map(|r| (r.msg(), r.kv("items"), r.kv("completed")))
+    ///     .collect();
+    /// assert_eq!(
+    ///     summaries,
+    ///     [
+    ///         ("consumer finished", Some("3"), Some("true")),
+    ///         ("consumer stopped", Some("1"), Some("false")),
+    ///     ],
+    /// );
+    /// assert!(records.iter().all(|r| r.kv("consumer") == Some("1")));
+    /// ```
+    #[cfg(feature = "futures-03")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "futures-03")))]
+    pub fn log_summary(mut self, level: Level, msg: &'static str) -> Self
+    where
+        F: Stream,
+    {
+        self.diagnostics_mut().summary = Some((level, msg));
+        self
+    }
+
+    /// Log a summary of how the wrapped value was polled once it completes.
+    ///
+    /// The record carries the number of `polls`, the time spent inside them
+    /// (`busy_us`) and between them (`idle_us`), the longest poll
+    /// (`max_poll_us`) and the time from first poll to completion
+    /// (`total_ms`). Nothing is logged if the wrapper is dropped before
+    /// completing, see [`log_cancellation`](Self::log_cancellation) for that:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::future;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    ///
+    /// let request = async { tokio::task::yield_now().await }
+    ///     .with_logger(&logger)
+    ///     .log_poll_stats(Level::Info, "request stats");
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// runtime.block_on(request);
+    ///
+    /// drop(
+    ///     future::pending::<()>()
+    ///         .with_logger(&logger)
+    ///         .log_poll_stats(Level::Info, "never logged"),
+    /// );
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// let stats = &records[0];
+    /// assert_eq!(stats.msg(), "request stats");
+    /// assert_eq!(stats.kv("polls"), Some("2"));
+    /// assert_eq!(stats.kv("request_id"), Some("1"));
+    ///
+    /// let micros = |key| stats.kv(key).unwrap().parse::<u128>().unwrap();
+    /// assert!(stats.kv("idle_us").is_some() && stats.kv("total_ms").is_some());
+    /// assert!(micros("max_poll_us") <= micros("busy_us"));
+    /// ```
+    pub fn log_poll_stats(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().poll_stats = Some(PollStats::new(level, msg));
+        self
+    }
+
+    /// Get the id given by [`tag_task`](Self::tag_task), if any.
+    pub fn task_id(&self) -> Option<u64> {
+        self.diagnostics.as_ref().and_then(|d| d.task_id)
+    }
+
+    fn diagnostics_mut(&mut self) -> &mut Diagnostics {
+        self.diagnostics.get_or_insert_with(Default::default)
+    }
+
+    /// Poll the inner future with the logger's scope active, running any
+    /// enabled diagnostics.
+    pub(crate) fn poll_instrumented(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output>
+    where
+        F: Future,
+    {
+        let this = self.project();
+        let inner = this.inner.as_pin_mut().expect(TAKEN_APART);
+        let logger = this.logger.as_ref().expect(TAKEN_APART).borrow();
+        let created = *this.created;
+
+        match this.diagnostics {
+            Some(diagnostics) => slog_scope::scope(logger, || {
+                diagnostics.poll(logger, created, cx, |cx| inner.poll(cx), Poll::is_ready)
+            }),
+            None => slog_scope::scope(logger, || inner.poll(cx)),
+        }
+    }
+
+    #[cfg(feature = "futures-03")]
+    /// Poll the inner stream with the logger's scope active, running any
+    /// enabled diagnostics.
+    pub(crate) fn poll_next_instrumented(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<F::Item>>
+    where
+        F: Stream,
+    {
+        let this = self.project();
+        let inner = this.inner.as_pin_mut().expect(TAKEN_APART);
+        let logger = this.logger.as_ref().expect(TAKEN_APART).borrow();
+        let created = *this.created;
+
+        match this.diagnostics {
+            Some(diagnostics) => slog_scope::scope(logger, || {
+                let poll = diagnostics.poll(
+                    logger,
+                    created,
+                    cx,
+                    |cx| inner.poll_next(cx),
+                    |poll| matches!(poll, Poll::Ready(None)),
+                );
+                if let Poll::Ready(Some(_)) = poll {
+                    diagnostics.items += 1;
+                }
+                poll
+            }),
+            None => slog_scope::scope(logger, || inner.poll_next(cx)),
+        }
+    }
+}
+
+impl<L> SlogScope<L, ()>
+where
+    L: Borrow<Logger>,
+{
+    /// Start building a wrapper with several diagnostics enabled.
+    ///
+    /// See [`SlogScopeBuilder`].
+    pub fn builder(logger: L) -> SlogScopeBuilder<L> {
+        SlogScopeBuilder {
+            logger,
+            inner: (),
+            diagnostics: None,
+        }
+    }
+}
+
+/// A builder for [`SlogScope`]s with diagnostics enabled.
+///
+/// Created by [`SlogScope::builder`]. The options are the same as the
+/// wrapper's own methods, and it can be cloned to wrap several futures the
+/// same way. Anything not enabled costs nothing:
+///
+/// ```
+/// # use slog::o;
+/// use std::{future, thread, time::Duration};
+/// use slog::Level;
+/// use slog_scope_futures::{test_util::capture, SlogScope};
+///
+/// let (logger, records) = capture();
+/// let logger = logger.new(o!("request_id" => 1));
+///
+/// let instrumented = SlogScope::builder(&logger)
+///     .log_completion(Level::Info, "request finished")
+///     .slow_poll_threshold(Duration::from_millis(20))
+///     .log_cancellation(Level::Warning, "request cancelled");
+///
+/// futures_executor::block_on(async {
+///     // Defaults log nothing.
+///     SlogScope::builder(&logger).wrap(async {}).await;
+///     assert!(records.is_empty());
+///
+///     // Options can be picked one by one...
+///     SlogScope::builder(&logger)
+///         .log_completion(Level::Debug, "done")
+///         .wrap(async {})
+///         .await;
+///     drop(SlogScope::builder(&logger).log_cancellation(Level::Debug, "dropped").build(async {}));
+///
+///     // ...or combined.
+///     instrumented.clone().wrap(async { thread::sleep(Duration::from_millis(40)) }).await;
+///     drop(instrumented.build(future::pending::<()>()));
+/// });
+///
+/// let records = records.records();
+/// let logged: Vec<_> = records.iter().map(|r| (r.level(), r.msg())).collect();
+/// assert_eq!(
+///     logged,
+///     [
+///         (Level::Debug, "done"),
+///         (Level::Debug, "dropped"),
+///         (Level::Warning, "slow poll"),
+///         (Level::Info, "request finished"),
+///         (Level::Warning, "request cancelled"),
+///     ],
+/// );
+/// ```
+///
+/// [`wrap`](Self::wrap) attaches the future without building the wrapper
+/// yet, so it can be awaited directly, while [`build`](Self::build) returns
+/// the wrapper, e.g. to pass it on.
+#[derive(Clone, Debug)]
+pub struct SlogScopeBuilder<L, F = ()> {
+    logger: L,
+    inner: F,
+    diagnostics: Option<Box<Diagnostics>>,
+}
+
+impl<L> SlogScopeBuilder<L>
+where
+    L: Borrow<Logger>,
+{
+    /// Wrap `inner`.
+    pub fn build<F>(self, inner: F) -> SlogScope<L, F> {
+        self.wrap(inner).into_scope()
+    }
+
+    /// Attach `inner`, to be wrapped once awaited.
+    pub fn wrap<F>(self, inner: F) -> SlogScopeBuilder<L, F> {
+        SlogScopeBuilder {
+            logger: self.logger,
+            inner,
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+impl<L, F> SlogScopeBuilder<L, F>
+where
+    L: Borrow<Logger>,
+{
+    /// See [`SlogScope::log_panics`].
+    pub fn log_panics(mut self) -> Self {
+        self.diagnostics_mut().log_panics = true;
+        self
+    }
+
+    /// See [`SlogScope::log_completion`].
+    pub fn log_completion(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().completion = Some((level, msg));
+        self
+    }
+
+    /// See [`SlogScope::log_cancellation`].
+    pub fn log_cancellation(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().cancellation = Some(Cancellation {
+            level,
+            msg,
+            unpolled: true,
+        });
+        self
+    }
+
+    /// See [`SlogScope::log_cancellation_if_polled`].
+    pub fn log_cancellation_if_polled(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().cancellation = Some(Cancellation {
+            level,
+            msg,
+            unpolled: false,
+        });
+        self
+    }
+
+    /// See [`SlogScope::slow_poll_threshold`].
+    pub fn slow_poll_threshold(mut self, threshold: Duration) -> Self {
+        self.diagnostics_mut().slow_poll = Some(threshold);
+        self
+    }
+
+    /// See [`SlogScope::log_schedule_delay`].
+    ///
+    /// The delay is counted from when the wrapper is built.
+    pub fn log_schedule_delay(mut self, level: Level, threshold: Duration) -> Self {
+        self.diagnostics_mut().schedule_delay = Some(ScheduleDelay { level, threshold });
+        self
+    }
+
+    /// See [`SlogScope::wake_latency_threshold`].
+    pub fn wake_latency_threshold(mut self, threshold: Duration) -> Self {
+        let diagnostics = self.diagnostics_mut();
+        diagnostics.wake_latency = Some(threshold);
+        diagnostics.wakes.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// See [`SlogScope::log_wake_counts`].
+    pub fn log_wake_counts(mut self, level: Level) -> Self {
+        let diagnostics = self.diagnostics_mut();
+        diagnostics.wake_counts = Some(level);
+        diagnostics.wakes.get_or_insert_with(Default::default);
+        self
+    }
+
+    /// See [`SlogScope::log_thread_migrations`].
+    pub fn log_thread_migrations(mut self) -> Self {
+        self.diagnostics_mut().last_thread.get_or_insert(None);
+        self
+    }
+
+    /// See [`SlogScope::log_poll_stats`].
+    pub fn log_poll_stats(mut self, level: Level, msg: &'static str) -> Self {
+        self.diagnostics_mut().poll_stats = Some(PollStats::new(level, msg));
+        self
+    }
+
+    fn diagnostics_mut(&mut self) -> &mut Diagnostics {
+        self.diagnostics.get_or_insert_with(Default::default)
+    }
+
+    fn into_scope(self) -> SlogScope<L, F> {
+        let mut scope = SlogScope::new(self.logger, self.inner);
+        scope.diagnostics = self.diagnostics;
+        scope
+    }
+}
+
+impl<L, F> IntoFuture for SlogScopeBuilder<L, F>
+where
+    L: Borrow<Logger>,
+    F: Future,
+{
+    type Output = F::Output;
+    type IntoFuture = SlogScope<L, F>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.into_scope()
+    }
+}
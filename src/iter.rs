@@ -0,0 +1,157 @@
+//! The synchronous sibling of the stream support: each step of a wrapped
+//! iterator runs in a slog scope, along with everything upstream of it.
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::iter::IteratorExt;
+//!
+//! let batch = slog::Logger::root(slog::Discard, o!("batch" => 1));
+//!
+//! let scopes: Vec<_> = ["a", "b"]
+//!     .iter()
+//!     .map(|row| format!("{} {:?}", row, slog_scope::logger()))
+//!     .with_logger(&batch)
+//!     .collect();
+//!
+//! assert_eq!(scopes, ["a Logger(batch)", "b Logger(batch)"]);
+//! ```
+
+use std::{borrow::Borrow, iter::FusedIterator};
+
+use slog::Logger;
+
+/// An `Iterator` wrapped in a slog scope.
+///
+/// Every call to `next` or `next_back` enters the scope, so closures given to
+/// adapters before the wrapper run in it. Adapters after the wrapper only run
+/// in it when the chain is driven by `fold` or `for_each`, which run whole in
+/// the scope. Put closures before the wrapper when they need it either way:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::iter::IteratorExt;
+///
+/// let batch = slog::Logger::root(slog::Discard, o!("batch" => 1));
+///
+/// let mut scopes = Vec::new();
+/// (0..2)
+///     .with_logger(&batch)
+///     .map(|_| format!("{:?}", slog_scope::logger()))
+///     .for_each(|scope| scopes.push(scope));
+/// assert_eq!(scopes, ["Logger(batch)"; 2]);
+///
+/// let mut rows = (0..2).with_logger(&batch).map(|_| format!("{:?}", slog_scope::logger()));
+/// assert_eq!(rows.next().unwrap(), "Logger()");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedIter<L, I> {
+    logger: L,
+    iter: I,
+}
+
+impl<L, I> ScopedIter<L, I>
+where
+    L: Borrow<Logger>,
+{
+    /// Wrap an iterator in a slog scope.
+    pub fn new(logger: L, iter: I) -> Self {
+        ScopedIter { logger, iter }
+    }
+
+    /// Get the logger.
+    pub fn logger(&self) -> &Logger {
+        self.logger.borrow()
+    }
+
+    /// Get a reference to the inner iterator.
+    pub fn get_ref(&self) -> &I {
+        &self.iter
+    }
+
+    /// Get a mutable reference to the inner iterator.
+    pub fn get_mut(&mut self) -> &mut I {
+        &mut self.iter
+    }
+
+    /// Unwrap the inner iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<L, I> Iterator for ScopedIter<L, I>
+where
+    L: Borrow<Logger>,
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let iter = &mut self.iter;
+        slog_scope::scope(self.logger.borrow(), || iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn fold<B, F>(self, init: B, f: F) -> B
+    where
+        F: FnMut(B, I::Item) -> B,
+    {
+        let iter = self.iter;
+        slog_scope::scope(self.logger.borrow(), || iter.fold(init, f))
+    }
+}
+
+impl<L, I> DoubleEndedIterator for ScopedIter<L, I>
+where
+    L: Borrow<Logger>,
+    I: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<I::Item> {
+        let iter = &mut self.iter;
+        slog_scope::scope(self.logger.borrow(), || iter.next_back())
+    }
+
+    fn rfold<B, F>(self, init: B, f: F) -> B
+    where
+        F: FnMut(B, I::Item) -> B,
+    {
+        let iter = self.iter;
+        slog_scope::scope(self.logger.borrow(), || iter.rfold(init, f))
+    }
+}
+
+impl<L, I> ExactSizeIterator for ScopedIter<L, I>
+where
+    L: Borrow<Logger>,
+    I: ExactSizeIterator,
+{
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<L, I> FusedIterator for ScopedIter<L, I>
+where
+    L: Borrow<Logger>,
+    I: FusedIterator,
+{
+}
+
+/// Convenience trait for wrapping an `Iterator` in a slog scope via method
+/// chaining.
+///
+/// Automatically implemented for all `Iterator`s.
+pub trait IteratorExt: Iterator + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> ScopedIter<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        ScopedIter::new(logger, self)
+    }
+}
+
+impl<I> IteratorExt for I where I: Iterator {}
@@ -0,0 +1,92 @@
+//! [`scoped_handler`] runs each invocation of a Lambda function under a
+//! logger carrying the invocation's request id and function metadata.
+//!
+//! ```
+//! # use slog::o;
+//! use lambda_runtime::{Context, Error, LambdaEvent, Service};
+//! use slog_scope_futures::{lambda::scoped_handler, FutureExt};
+//!
+//! async fn handler(event: String, _: Context) -> Result<String, Error> {
+//!     Ok(format!("{} {:?}", event, slog_scope::logger()))
+//! }
+//!
+//! let base = slog::Logger::root(slog::Discard, o!("app" => "billing"));
+//!
+//! # #[allow(dead_code)]
+//! async fn run(base: slog::Logger) -> Result<(), Error> {
+//!     // Cold start logs, and the runtime itself, use the base logger.
+//!     slog::info!(base, "starting");
+//!     lambda_runtime::run(scoped_handler(base.clone(), handler))
+//!         .with_logger(&base)
+//!         .await
+//! }
+//!
+//! let mut service = scoped_handler(base, handler);
+//! let mut context = Context::default();
+//! context.request_id = "8476a536".into();
+//!
+//! let out = futures_executor::block_on(service.call(LambdaEvent::new("ping".into(), context)));
+//! assert_eq!(
+//!     out.unwrap(),
+//!     "ping Logger(invoked_function_arn, function_version, function_name, aws_request_id, app)",
+//! );
+//! ```
+//!
+//! [`scoped_handler`]: crate::lambda::scoped_handler
+
+use std::{
+    future::Future,
+    task::{Context as TaskContext, Poll},
+};
+
+use lambda_runtime::{Context, LambdaEvent, Service};
+use slog::{o, Logger};
+
+use super::SlogScope;
+
+/// Adapt `f` into a Lambda service running each invocation in a scope.
+///
+/// The invocation's logger is a child of `logger` carrying
+/// `aws_request_id`, `function_name`, `function_version` and
+/// `invoked_function_arn` KVs.
+pub fn scoped_handler<F>(logger: Logger, f: F) -> ScopedHandler<F> {
+    ScopedHandler { logger, f }
+}
+
+/// A Lambda service running each invocation in a scope.
+///
+/// See [`scoped_handler`].
+#[derive(Clone, Debug)]
+pub struct ScopedHandler<F> {
+    logger: Logger,
+    f: F,
+}
+
+impl<A, F, Fut, R, E> Service<LambdaEvent<A>> for ScopedHandler<F>
+where
+    F: FnMut(A, Context) -> Fut,
+    Fut: Future<Output = Result<R, E>>,
+{
+    type Response = R;
+    type Error = E;
+    type Future = SlogScope<Logger, Fut>;
+
+    fn poll_ready(&mut self, _: &mut TaskContext<'_>) -> Poll<Result<(), E>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, event: LambdaEvent<A>) -> Self::Future {
+        let LambdaEvent { payload, context } = event;
+        let logger = self.logger.new(o!(
+            "aws_request_id" => context.request_id.clone(),
+            "function_name" => context.env_config.function_name.clone(),
+            "function_version" => context.env_config.version.clone(),
+            "invoked_function_arn" => context.invoked_function_arn.clone(),
+        ));
+
+        let f = &mut self.f;
+        let future = slog_scope::scope(&logger, || f(payload, context));
+
+        SlogScope::new(logger, future)
+    }
+}
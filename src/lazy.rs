@@ -0,0 +1,300 @@
+//! Wrappers building their logger when first polled.
+//! [`WithKv`](crate::lazy::WithKv) derives it from the scope it's first
+//! polled in, rather than the one it's created in:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::FutureExt;
+//!
+//! async fn fetch(url: &str) -> String {
+//!     format!("{} {:?}", url, slog_scope::logger())
+//! }
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let fetch = fetch("/users").with_kv(o!("step" => "fetch"));
+//!
+//! let out = futures_executor::block_on(async { fetch.await }.with_logger(request));
+//! assert_eq!(out, "/users Logger(step, request_id)");
+//! ```
+//!
+//! [`InheritKv`](crate::lazy::InheritKv) goes further, and layers its KVs
+//! onto whichever scope it's polled in each time.
+
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use slog::{o, Logger, OwnedKV, OwnedKVList, SendSyncRefUnwindSafeKV};
+
+use super::SlogScope;
+
+pin_project! {
+    /// A `Future` running under a child of the logger it's first polled with.
+    ///
+    /// The child logger is built on the first poll and reused afterwards, even
+    /// if later polls happen under another scope:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::task::{Context, Poll};
+    /// use futures_util::{future, task::noop_waker, FutureExt as _};
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let first = slog::Logger::root(slog::Discard, o!("first" => 1));
+    /// let second = slog::Logger::root(slog::Discard, o!("second" => 1));
+    ///
+    /// let mut scopes = Vec::new();
+    /// let mut fut = future::poll_fn(|_| {
+    ///     scopes.push(format!("{:?}", slog_scope::logger()));
+    ///     if scopes.len() < 2 { Poll::Pending } else { Poll::Ready(()) }
+    /// })
+    /// .with_kv(o!("step" => 1));
+    ///
+    /// let waker = noop_waker();
+    /// let mut cx = Context::from_waker(&waker);
+    /// slog_scope::scope(&first, || assert!(fut.poll_unpin(&mut cx).is_pending()));
+    /// slog_scope::scope(&second, || assert!(fut.poll_unpin(&mut cx).is_ready()));
+    ///
+    /// drop(fut);
+    /// assert_eq!(scopes, ["Logger(step, first)", "Logger(step, first)"]);
+    /// ```
+    pub struct WithKv<T, F>
+    where
+        T: SendSyncRefUnwindSafeKV,
+    {
+        // The inner future is only pinned once it's moved into `scoped`.
+        pending: Option<(OwnedKV<T>, F)>,
+        #[pin]
+        scoped: Option<SlogScope<Logger, F>>,
+    }
+}
+
+impl<T, F> WithKv<T, F>
+where
+    T: SendSyncRefUnwindSafeKV,
+{
+    pub(crate) fn new(values: OwnedKV<T>, inner: F) -> Self {
+        WithKv {
+            pending: Some((values, inner)),
+            scoped: None,
+        }
+    }
+}
+
+impl<T, F> Future for WithKv<T, F>
+where
+    T: SendSyncRefUnwindSafeKV + 'static,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Some((values, inner)) = this.pending.take() {
+            let logger = slog_scope::logger().new(values);
+            this.scoped.set(Some(SlogScope::new(logger, inner)));
+        }
+
+        this.scoped
+            .as_pin_mut()
+            .expect("set on first poll")
+            .poll(cx)
+    }
+}
+
+pin_project! {
+    /// A `Future` running under a logger built on its first poll.
+    ///
+    /// The logger is built by calling `make_logger` once, and cached for later
+    /// polls. If the future is dropped without being polled, `make_logger`
+    /// never runs:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{cell::Cell, task::Poll};
+    /// use futures_util::future;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let calls = Cell::new(0);
+    /// let make_logger = || {
+    ///     calls.set(calls.get() + 1);
+    ///     slog::Logger::root(slog::Discard, o!("attempt" => 2))
+    /// };
+    ///
+    /// drop(future::ready(()).with_logger_fn(make_logger));
+    /// assert_eq!(calls.get(), 0);
+    ///
+    /// let mut polls = 0;
+    /// let retry = future::poll_fn(|cx| {
+    ///     assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(attempt)");
+    ///     polls += 1;
+    ///     if polls < 5 {
+    ///         cx.waker().wake_by_ref();
+    ///         Poll::Pending
+    ///     } else {
+    ///         Poll::Ready(())
+    ///     }
+    /// });
+    ///
+    /// futures_executor::block_on(retry.with_logger_fn(make_logger));
+    /// assert_eq!(calls.get(), 1);
+    /// ```
+    pub struct WithLoggerFn<M, F> {
+        // The inner future is only pinned once it's moved into `scoped`.
+        pending: Option<(M, F)>,
+        #[pin]
+        scoped: Option<SlogScope<Logger, F>>,
+    }
+}
+
+impl<M, F> WithLoggerFn<M, F> {
+    pub(crate) fn new(make_logger: M, inner: F) -> Self {
+        WithLoggerFn {
+            pending: Some((make_logger, inner)),
+            scoped: None,
+        }
+    }
+}
+
+impl<M, F> Future for WithLoggerFn<M, F>
+where
+    M: FnOnce() -> Logger,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        if let Some((make_logger, inner)) = this.pending.take() {
+            this.scoped.set(Some(SlogScope::new(make_logger(), inner)));
+        }
+
+        this.scoped
+            .as_pin_mut()
+            .expect("set on first poll")
+            .poll(cx)
+    }
+}
+
+pin_project! {
+    /// A `Future` running under a child of whichever logger is active when
+    /// it's polled.
+    ///
+    /// Unlike [`WithKv`], the KVs are layered onto the scope of every poll,
+    /// so a library can tag its internal futures with a component while
+    /// keeping the caller's context:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::task::{Context, Poll};
+    /// use futures_util::{future, task::noop_waker, FutureExt as _};
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let first = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    /// let second = slog::Logger::root(slog::Discard, o!("job" => 1));
+    ///
+    /// let mut scopes = Vec::new();
+    /// let mut fut = future::poll_fn(|_| {
+    ///     scopes.push(format!("{:?}", slog_scope::logger()));
+    ///     if scopes.len() < 3 { Poll::Pending } else { Poll::Ready(()) }
+    /// })
+    /// .inherit_kv(o!("component" => "pool"));
+    ///
+    /// let waker = noop_waker();
+    /// let mut cx = Context::from_waker(&waker);
+    /// slog_scope::scope(&first, || assert!(fut.poll_unpin(&mut cx).is_pending()));
+    /// slog_scope::scope(&second, || assert!(fut.poll_unpin(&mut cx).is_pending()));
+    /// slog_scope::scope(&first, || assert!(fut.poll_unpin(&mut cx).is_ready()));
+    ///
+    /// drop(fut);
+    /// assert_eq!(
+    ///     scopes,
+    ///     [
+    ///         "Logger(component, request_id)",
+    ///         "Logger(component, job)",
+    ///         "Logger(component, request_id)",
+    ///     ],
+    /// );
+    /// ```
+    ///
+    /// The child logger is cached, and only rebuilt when the active logger
+    /// changes between polls. Loggers are told apart by their key-value list,
+    /// and the cache holds a clone of the one it was built from, so a new
+    /// logger can't be mistaken for it even when it lives at the same address.
+    pub struct InheritKv<T, F>
+    where
+        T: SendSyncRefUnwindSafeKV,
+    {
+        values: Arc<OwnedKV<T>>,
+        // The active logger at the last rebuild, and the child built from it.
+        cache: Option<(Logger, Logger)>,
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<T, F> InheritKv<T, F>
+where
+    T: SendSyncRefUnwindSafeKV,
+{
+    pub(crate) fn new(values: OwnedKV<T>, inner: F) -> Self {
+        InheritKv {
+            values: Arc::new(values),
+            cache: None,
+            inner,
+        }
+    }
+}
+
+impl<T, F> Future for InheritKv<T, F>
+where
+    T: SendSyncRefUnwindSafeKV + 'static,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        slog_scope::with_logger(|current| {
+            let stale = match this.cache {
+                Some((parent, _)) => !same_list(parent, current),
+                None => true,
+            };
+            if stale {
+                let child = current.new(o!(Arc::clone(this.values)));
+                *this.cache = Some((current.clone(), child));
+            }
+        });
+
+        let inner = this.inner;
+        let (_, logger) = this.cache.as_ref().expect("set above");
+        slog_scope::scope(logger, || inner.poll(cx))
+    }
+}
+
+type ListNode = Arc<dyn SendSyncRefUnwindSafeKV + 'static>;
+
+// `OwnedKVList` is a single `Arc` to the head of its list, and `slog` has no
+// accessor for it. These make sure its layout still matches before `same_list`
+// reads it as one.
+const _: () = assert!(mem::size_of::<OwnedKVList>() == mem::size_of::<ListNode>());
+const _: () = assert!(mem::align_of::<OwnedKVList>() == mem::align_of::<ListNode>());
+
+/// Returns whether both loggers share the same key-value list node.
+fn same_list(a: &Logger, b: &Logger) -> bool {
+    fn node(list: &OwnedKVList) -> &ListNode {
+        // SAFETY: `OwnedKVList` has exactly one field, a `ListNode`, and the
+        // assertions above rule out any other layout for it.
+        unsafe { &*(list as *const OwnedKVList as *const ListNode) }
+    }
+
+    Arc::ptr_eq(node(a.list()), node(b.list()))
+}
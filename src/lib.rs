@@ -18,7 +18,6 @@
 //! This compiles, but doesn't do what you actually want:
 //!
 //! ```no_run
-//! # #![feature(async_await)]
 //! # async fn some_operation() {}
 //! # async {
 //! # use slog::o;
@@ -32,6 +31,22 @@
 //! # };
 //! ```
 //!
+//! Instead, [`scoped`] is the async counterpart of `slog_scope::scope`:
+//!
+//! ```
+//! # async fn some_operation() -> String { format!("{:?}", slog_scope::logger()) }
+//! # use slog::o;
+//! use slog_scope_futures::scoped;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("name" => "sub logger"));
+//!
+//! let fut = scoped(&logger, async {
+//!     some_operation().await
+//! });
+//!
+//! assert_eq!(futures_executor::block_on(fut), "Logger(name)");
+//! ```
+//!
 //! ## The Solution
 //!
 //! Rather than using a closure to represent a slog scope, the logger must
@@ -43,8 +58,7 @@
 //!
 //! Using the wrapper directly:
 //!
-//! ```rust,norun
-//! # #![feature(async_await)]
+//! ```no_run
 //! # async fn some_operation() {}
 //! # async {
 //! # use slog::o;
@@ -58,8 +72,7 @@
 //!
 //! Using the convenience trait:
 //!
-//! ```rust,norun
-//! # #![feature(async_await)]
+//! ```no_run
 //! # async fn some_operation() {}
 //! # async {
 //! # use slog::o;
@@ -81,8 +94,7 @@
 //! Because the `SlogScope` wrapper takes any `L: Borrow<Logger>`, you can
 //! create it with either an owned *or* a borrowed `Logger`.
 //!
-//! ```rust,norun
-//! # #![feature(async_await)]
+//! ```no_run
 //! # async fn some_operation() {}
 //! # async fn some_other_operation() {}
 //! # async {
@@ -101,30 +113,1091 @@
 //! # };
 //! ```
 //!
+//! The wrapper is `Send` exactly when both the logger and the wrapped future
+//! are, and the same goes for `Sync`, whatever diagnostics are enabled. A
+//! borrowed logger is `Send`, but only a `'static` one can be spawned onto
+//! another thread:
+//!
+//! ```compile_fail
+//! # use slog::o;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+//! let logger = slog::Logger::root(slog::Discard, o!());
+//!
+//! runtime.spawn(async {}.with_logger(&logger));
+//! ```
+//!
+//! A future that isn't `Send` can't be spawned there either, scoped or not:
+//!
+//! ```compile_fail
+//! # use slog::o;
+//! use std::rc::Rc;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+//! let logger = slog::Logger::root(slog::Discard, o!());
+//! let shared = Rc::new(1);
+//!
+//! runtime.spawn(async move { *shared }.with_logger(logger));
+//! ```
+//!
+//! Spawning many tasks under the same logger is cheapest with a
+//! [`SharedLogger`](crate::shared::SharedLogger), see the [`shared`] module.
+//!
+//! Either way, entering the scope on each poll doesn't clone the logger, as
+//! slog-scope only keeps a pointer to it. Nesting wrappers, e.g. at the
+//! request, service and client layers, costs a few nanoseconds per layer, and
+//! the innermost logger is always the active one:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let client = request.new(o!("client" => "db"));
+//!
+//! let fut = async {
+//!     let shared = async { format!("{:?}", slog_scope::logger()) }.with_logger(&request);
+//!     let inner = async { format!("{:?}", slog_scope::logger()) }.with_logger(&request);
+//!     let nested = inner.with_logger(&client).with_logger(&request);
+//!     (shared.await, nested.await)
+//! };
+//!
+//! let out = futures_executor::block_on(fut.with_logger(&request).with_logger(&request));
+//! assert_eq!(out, ("Logger(request_id)".to_owned(), "Logger(request_id)".to_owned()));
+//!
+//! let fut = async { format!("{:?}", slog_scope::logger()) }.with_logger(&request);
+//! assert_eq!(futures_executor::block_on(fut.with_logger(&client)), "Logger(request_id)");
+//! ```
+//!
+//! ### Streams
+//!
+//! `SlogScope` is also a `Stream` when wrapping one, entering the scope for
+//! every call to `poll_next`. Anything driven from inside the wrapper, such as
+//! the closures of combinators it wraps, sees the scoped logger for each item.
+//!
+//! ```
+//! # use slog::o;
+//! use futures_util::stream::{self, StreamExt as _};
+//! use slog_scope_futures::futures03::StreamExt;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("name" => "sub logger"));
+//!
+//! let items = stream::iter(1..=3)
+//!     .map(|i| {
+//!         assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(name)");
+//!         i * 2
+//!     })
+//!     .with_logger(logger);
+//!
+//! let items: Vec<_> = futures_executor::block_on(items.collect());
+//! assert_eq!(items, [2, 4, 6]);
+//! ```
+//!
+//! ### Sinks
+//!
+//! Likewise, a wrapped `Sink` runs `poll_ready`, `start_send`, `poll_flush`
+//! and `poll_close` inside the scope.
+//!
+//! ```
+//! # use slog::o;
+//! # use std::{pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+//! use futures_util::sink::SinkExt as _;
+//! use slog_scope_futures::futures03::SinkExt;
+//!
+//! /// A sink recording the active scope whenever it's driven.
+//! struct Recorder(Arc<Mutex<Vec<String>>>);
+//!
+//! impl Recorder {
+//!     fn record(self: Pin<&mut Self>) {
+//!         self.0.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+//!     }
+//! }
+//!
+//! impl futures_sink::Sink<u32> for Recorder {
+//!     type Error = ();
+//!
+//!     fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+//!         self.record();
+//!         Poll::Ready(Ok(()))
+//!     }
+//!
+//!     fn start_send(self: Pin<&mut Self>, _: u32) -> Result<(), ()> {
+//!         self.record();
+//!         Ok(())
+//!     }
+//!
+//!     fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+//!         self.record();
+//!         Poll::Ready(Ok(()))
+//!     }
+//!
+//!     fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+//!         self.record();
+//!         Poll::Ready(Ok(()))
+//!     }
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+//! let records = Arc::new(Mutex::new(Vec::new()));
+//! let mut sink = Recorder(records.clone()).with_logger(logger);
+//!
+//! futures_executor::block_on(async {
+//!     sink.send(1).await.unwrap();
+//!     sink.close().await.unwrap();
+//! });
+//!
+//! let records = records.lock().unwrap();
+//! assert_eq!(records.len(), 4);
+//! assert!(records.iter().all(|r| r == "Logger(conn)"));
+//! ```
+//!
+//! ### Blocking on Futures
+//!
+//! At the boundary between synchronous and async code, [`block_on_scoped`]
+//! and [`block_on_in_current_scope`] run a future to completion under a
+//! logger, which then applies across every `.await` inside it:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::block_on_scoped;
+//!
+//! async fn inner() -> String {
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! async fn outer() -> String {
+//!     inner().await
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("cli" => "sync"));
+//! assert_eq!(block_on_scoped(&logger, outer()), "Logger(cli)");
+//! ```
+//!
 
 #![warn(missing_docs)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(all(feature = "nightly-async-iter", nightly), feature(async_iterator))]
 
-/// An implementation of `std::future` for `SlogScope`
+/// Scoped spawning onto `actix` arbiters
+#[cfg(feature = "actix")]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+pub mod actix;
+/// Middleware scoping `actix-web` requests
+#[cfg(feature = "actix-web")]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix-web")))]
+pub mod actix_web;
+/// Scoped task spawning for `async-executor`
+#[cfg(feature = "async-executor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-executor")))]
+pub mod async_executor;
+/// Slog scopes for nightly's `AsyncIterator`
+#[cfg(all(feature = "nightly-async-iter", nightly))]
+#[cfg_attr(docsrs, doc(cfg(feature = "nightly-async-iter")))]
+pub mod async_iter;
+/// Scoped task spawning for `async-std`
+#[cfg(feature = "async-std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-std")))]
+pub mod async_std;
+/// Middleware and extractors for `axum`
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub mod axum;
+/// Channels carrying the sender's logger with each message
+#[cfg(feature = "futures-channel")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-channel")))]
+pub mod channel;
+/// Slog scopes for synchronous closures
+pub mod closure;
+/// Deprecated home of the futures 0.1 extension trait
+#[cfg(feature = "futures-01")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-01")))]
+#[deprecated(since = "0.2.0", note = "use the `futures01` module instead")]
 pub mod future01;
-/// An implementation of `futures crate` for `SlogScope`
+/// Deprecated home of the `std::future` extension trait
+#[deprecated(since = "0.2.0", note = "use `slog_scope_futures::FutureExt` instead")]
 pub mod future03;
+/// Implementations of `futures` 0.1 traits for `SlogScope`
+#[cfg(feature = "futures-01")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-01")))]
+pub mod futures01;
+/// Implementations of `futures` 0.3 traits for `SlogScope`
+#[cfg(feature = "futures-03")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-03")))]
+pub mod futures03;
+/// An implementation of `http_body::Body` for `SlogScope`
+#[cfg(feature = "http-body")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http-body")))]
+pub mod http_body;
+/// Opt-in diagnostics for scoped futures
+pub mod instrument;
+/// Slog scopes for synchronous iterators
+pub mod iter;
+/// Lambda handlers running each invocation in a scope
+#[cfg(feature = "lambda")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lambda")))]
+pub mod lambda;
+/// Wrappers deriving their logger when first polled
+pub mod lazy;
+/// Futures wrapped in a slog scope only if given a logger
+pub mod optional;
+/// Logging the outcome of futures
+pub mod outcome;
+/// Slog scopes for `rayon` thread pools and parallel iterators
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub mod rayon;
+/// Retrying operations with a scope per attempt
+pub mod retry;
+/// A fairing and handler wrapper scoping `rocket` requests
+#[cfg(feature = "rocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+pub mod rocket;
+/// A cheaply cloned logger handle for fanning out to many tasks
+pub mod shared;
+/// The `std::future::Future` implementation for `SlogScope`
+pub mod std_future;
+/// Scoped serving and calling of `tarpc` requests
+#[cfg(feature = "tarpc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tarpc")))]
+pub mod tarpc;
+/// Capturing log records in tests
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod test_util;
+/// Scoped OS thread spawning
+pub mod thread;
+/// A middleware running `tide` requests under loggers of their own
+#[cfg(feature = "tide")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tide")))]
+pub mod tide;
+/// Implementations of `tokio` traits for `SlogScope`, and scoped task spawning
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod tokio;
+/// Slog scopes for `tokio-util` codecs
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+pub mod tokio_util;
+/// A layer scoping `tonic` RPCs
+#[cfg(feature = "tonic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tonic")))]
+pub mod tonic;
+/// An implementation of `tower::Service` for `SlogScope`
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub mod tower;
+/// Entering `tracing` spans along with slog scopes
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub mod tracing;
+/// A filter scoping `warp` handlers
+#[cfg(feature = "warp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "warp")))]
+pub mod warp;
+/// Scoped spawning onto the JS event loop on wasm
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "wasm", target_arch = "wasm32"))))]
+pub mod wasm;
 
-use std::borrow::Borrow;
+use std::{borrow::Borrow, fmt, pin::Pin};
 
+use pin_project_lite::pin_project;
 use slog::Logger;
 
-/// A `Future` wrapped in a slog scope.
-pub struct SlogScope<L, F> {
-    logger: L,
-    inner: F,
+use instrument::{Created, Diagnostics};
+
+pub use std_future::FutureExt;
+
+/// Run the body of an async function in a slog scope
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use slog_scope_futures_macros::slog_scope;
+
+/// Set up the global logger and run `async fn main` in its scope
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use slog_scope_futures_macros::main;
+
+/// Run an async test under a logger of its own, capturing what it logs
+#[cfg(all(feature = "macros", feature = "test-util"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "test-util"))))]
+pub use slog_scope_futures_macros::test;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(any(feature = "futures-03", feature = "tokio", feature = "async-std"))]
+    use std::future::Future;
+    use std::{
+        fmt,
+        panic::{RefUnwindSafe, UnwindSafe},
+    };
+
+    #[cfg(feature = "futures-03")]
+    pub use futures_util::{join, select, try_join};
+    use slog::Drain;
+    pub use slog::{o, Logger};
+    pub use slog_scope::scope;
+    use slog_scope::GlobalLoggerGuard;
+
+    pub fn current_logger() -> slog::Logger {
+        slog_scope::logger()
+    }
+
+    pub fn root_logger<D>(drain: D) -> Logger
+    where
+        D: Drain<Ok = ()> + Send + Sync + RefUnwindSafe + UnwindSafe + 'static,
+        D::Err: fmt::Debug,
+    {
+        Logger::root(drain.fuse(), o!())
+    }
+
+    pub fn set_global_logger(logger: Logger) -> GlobalLoggerGuard {
+        slog_scope::set_global_logger(logger)
+    }
+
+    #[cfg(feature = "futures-03")]
+    pub fn block_on_futures<F: Future>(future: F) -> F::Output {
+        futures_executor::block_on(future)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn block_on_tokio<F: Future>(
+        multi_thread: bool,
+        worker_threads: Option<usize>,
+        future: F,
+    ) -> F::Output {
+        let mut builder = if multi_thread {
+            tokio::runtime::Builder::new_multi_thread()
+        } else {
+            tokio::runtime::Builder::new_current_thread()
+        };
+        if let Some(n) = worker_threads {
+            builder.worker_threads(n);
+        }
+
+        match builder.enable_all().build() {
+            Ok(runtime) => runtime.block_on(future),
+            Err(err) => panic!("failed to build the tokio runtime: {}", err),
+        }
+    }
+
+    #[cfg(feature = "async-std")]
+    pub fn block_on_async_std<F: Future>(future: F) -> F::Output {
+        async_std::task::block_on(future)
+    }
 }
 
+pin_project! {
+    /// A `Future` wrapped in a slog scope.
+    ///
+    /// The wrapper is `Unpin` whenever the wrapped value is, regardless of
+    /// the logger, so it can be polled through `&mut` references:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, StreamExt as _};
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let mut items = stream::iter(1..=3).with_logger(&logger);
+    ///
+    /// futures_executor::block_on(async {
+    ///     assert_eq!(items.next().await, Some(1));
+    ///     assert_eq!(items.next().await, Some(2));
+    /// });
+    /// ```
+    ///
+    /// In particular, the standard impls of `Future` and `Stream` for `&mut F`
+    /// apply to it, so a scoped future can be polled repeatedly without being
+    /// consumed, e.g. from a `select!` loop or by hand:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::task::{Context, Poll};
+    /// use futures_util::{future, task::noop_waker, FutureExt as _};
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let mut polls = 0;
+    /// let mut handshake = future::poll_fn(|_| {
+    ///     assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(conn)");
+    ///     polls += 1;
+    ///     if polls < 3 { Poll::Pending } else { Poll::Ready(polls) }
+    /// })
+    /// .with_logger(&logger);
+    ///
+    /// let waker = noop_waker();
+    /// let mut cx = Context::from_waker(&waker);
+    /// assert!(handshake.poll_unpin(&mut cx).is_pending());
+    /// assert!(handshake.poll_unpin(&mut cx).is_pending());
+    /// assert_eq!(handshake.poll_unpin(&mut cx), Poll::Ready(3));
+    /// ```
+    ///
+    /// Wrapping a `!Unpin` value requires pinning the wrapper, as usual:
+    ///
+    /// ```compile_fail
+    /// # use slog::o;
+    /// use slog_scope_futures::SlogScope;
+    ///
+    /// fn assert_unpin<T: Unpin>(_: &T) {}
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!());
+    /// assert_unpin(&SlogScope::new(logger, async {}));
+    /// ```
+    ///
+    /// It's also `Clone` whenever both the logger and the wrapped value are,
+    /// with either owned or borrowed loggers:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::future;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("probe" => 1));
+    ///
+    /// let owned = future::ready(1).with_logger(logger.clone());
+    /// let borrowed = future::ready(2).with_logger(&logger);
+    ///
+    /// futures_executor::block_on(async {
+    ///     assert_eq!(owned.clone().await + owned.await, 2);
+    ///     assert_eq!(borrowed.clone().await + borrowed.await, 4);
+    /// });
+    /// ```
+    ///
+    /// The inner value is dropped inside the scope as well, so destructors
+    /// logging on cancellation still carry the scope's KVs:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// # use std::sync::{Arc, Mutex};
+    /// use std::task::Context;
+    /// use futures_util::{future, task::noop_waker, FutureExt as _};
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// /// A guard recording the active scope when dropped.
+    /// struct Guard(Arc<Mutex<Option<String>>>);
+    ///
+    /// impl Drop for Guard {
+    ///     fn drop(&mut self) {
+    ///         *self.0.lock().unwrap() = Some(format!("{:?}", slog_scope::logger()));
+    ///     }
+    /// }
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("transfer" => 1));
+    /// let dropped = Arc::new(Mutex::new(None));
+    /// let guard = Guard(dropped.clone());
+    /// let mut transfer = Box::pin(
+    ///     async move {
+    ///         let _guard = guard;
+    ///         future::pending::<()>().await
+    ///     }
+    ///     .with_logger(logger),
+    /// );
+    ///
+    /// let waker = noop_waker();
+    /// assert!(transfer.poll_unpin(&mut Context::from_waker(&waker)).is_pending());
+    /// drop(transfer);
+    ///
+    /// assert_eq!(dropped.lock().unwrap().as_deref(), Some("Logger(transfer)"));
+    /// ```
+    #[derive(Clone)]
+    pub struct SlogScope<L, F>
+    where
+        L: Borrow<Logger>,
+    {
+        // Both are only ever `None` once the wrapper is being taken apart or
+        // dropped.
+        logger: Option<L>,
+        #[pin]
+        inner: Option<F>,
+        diagnostics: Option<Box<Diagnostics>>,
+        created: Created,
+    }
+
+    impl<L, F> PinnedDrop for SlogScope<L, F>
+    where
+        L: Borrow<Logger>,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            let mut inner = this.inner;
+
+            if let Some(logger) = this.logger {
+                let logger = (*logger).borrow();
+                if let Some(diagnostics) = this.diagnostics {
+                    diagnostics.on_drop(logger);
+                }
+                slog_scope::scope(logger, || inner.set(None));
+            }
+        }
+    }
+}
+
+const TAKEN_APART: &str = "SlogScope used after being taken apart";
+
 impl<L, F> SlogScope<L, F>
 where
     L: Borrow<Logger>,
 {
     /// Wrap a `Future` in a slog scope.
     pub fn new(logger: L, inner: F) -> Self {
-        SlogScope { logger, inner }
+        SlogScope {
+            logger: Some(logger),
+            inner: Some(inner),
+            diagnostics: None,
+            created: Created::now(),
+        }
+    }
+
+    /// Get the logger whose scope is entered.
+    pub fn logger(&self) -> &Logger {
+        self.logger.as_ref().expect(TAKEN_APART).borrow()
+    }
+
+    /// Get a mutable reference to the stored logger.
+    pub fn logger_mut(&mut self) -> &mut L {
+        self.logger.as_mut().expect(TAKEN_APART)
+    }
+
+    /// Replace the stored logger, dropping the previous one.
+    ///
+    /// The new logger's scope is entered from the next poll on, e.g. to add
+    /// KVs learned halfway through a connection:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream::{self, StreamExt as _};
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// let conn = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let mut messages = stream::repeat_with(|| format!("{:?}", slog_scope::logger()))
+    ///     .with_logger(conn.clone());
+    ///
+    /// futures_executor::block_on(async {
+    ///     assert_eq!(messages.next().await.unwrap(), "Logger(conn)");
+    ///
+    ///     messages.set_logger(conn.new(o!("user_id" => 42)));
+    ///     assert_eq!(messages.next().await.unwrap(), "Logger(user_id, conn)");
+    /// });
+    /// ```
+    pub fn set_logger(&mut self, logger: L) {
+        *self.logger_mut() = logger;
+    }
+
+    /// Get a reference to the inner value.
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::stream;
+    /// use slog_scope_futures::futures03::StreamExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!());
+    /// let items = stream::iter([1, 2, 3]).with_logger(logger);
+    ///
+    /// assert_eq!(futures_core::Stream::size_hint(items.get_ref()), (3, Some(3)));
+    /// ```
+    pub fn get_ref(&self) -> &F {
+        self.inner.as_ref().expect(TAKEN_APART)
+    }
+
+    /// Get a mutable reference to the inner value.
+    ///
+    /// Care must be taken not to disturb the state of a value that's already
+    /// being polled.
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use futures_util::future;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!());
+    /// let mut fut = future::ready(1).with_logger(logger);
+    ///
+    /// *fut.get_mut() = future::ready(2);
+    /// assert_eq!(futures_executor::block_on(fut), 2);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut F {
+        self.inner.as_mut().expect(TAKEN_APART)
+    }
+
+    /// Get a pinned mutable reference to the inner value.
+    ///
+    /// Unlike polling the wrapper, this doesn't enter the scope:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use futures_util::task::noop_waker;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let mut fut = pin!(async { format!("{:?}", slog_scope::logger()) }.with_logger(logger));
+    ///
+    /// let waker = noop_waker();
+    /// let out = fut.as_mut().get_pin_mut().poll(&mut Context::from_waker(&waker));
+    /// assert_eq!(out, Poll::Ready("Logger()".to_owned()));
+    /// ```
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut F> {
+        self.project().inner.as_pin_mut().expect(TAKEN_APART)
+    }
+
+    /// Unwrap the inner value, dropping the logger.
+    ///
+    /// This consumes the wrapper by value, so it can only be called if it was
+    /// never pinned, or if it's `Unpin`:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let request = slog::Logger::root(slog::Discard, o!("request" => 1));
+    /// let retry = slog::Logger::root(slog::Discard, o!("retry" => 1));
+    ///
+    /// let fut = async { format!("{:?}", slog_scope::logger()) };
+    /// let fut = fut.with_logger(request).into_inner().with_logger(retry);
+    ///
+    /// assert_eq!(futures_executor::block_on(fut), "Logger(retry)");
+    /// ```
+    pub fn into_inner(self) -> F {
+        self.into_parts().1
+    }
+
+    /// Unwrap both the logger and the inner value.
+    ///
+    /// Like [`into_inner`](Self::into_inner), this consumes the wrapper by
+    /// value. Together with [`new`](Self::new), it lets a wrapper be taken
+    /// apart and rebuilt:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog_scope_futures::{FutureExt, SlogScope};
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("op" => "upload"));
+    /// let fut = async { format!("{:?}", slog_scope::logger()) }.with_logger(logger);
+    ///
+    /// let (logger, fut) = fut.into_parts();
+    /// assert_eq!(format!("{:?}", logger), "Logger(op)");
+    ///
+    /// let out = futures_executor::block_on(SlogScope::new(logger, fut));
+    /// assert_eq!(out, "Logger(op)");
+    /// ```
+    pub fn into_parts(mut self) -> (L, F) {
+        let logger = self.logger.take().expect(TAKEN_APART);
+        let inner = self.inner.take().expect(TAKEN_APART);
+
+        (logger, inner)
+    }
+
+    /// Transform the stored logger, keeping the inner value and any enabled
+    /// diagnostics.
+    ///
+    /// This consumes the wrapper by value, same as
+    /// [`into_parts`](Self::into_parts). It can promote a borrowed logger to
+    /// an owned one before spawning, or add KVs:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::future::Future;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// fn assert_static<F: Future + 'static>(f: F) -> F { f }
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    /// let fut = async { format!("{:?}", slog_scope::logger()) }.with_logger(&logger);
+    ///
+    /// let fut = fut
+    ///     .map_logger(|logger| logger.clone())
+    ///     .map_logger(|logger| logger.new(o!("phase" => "retry")));
+    ///
+    /// let out = futures_executor::block_on(assert_static(fut));
+    /// assert_eq!(out, "Logger(phase, conn)");
+    /// ```
+    pub fn map_logger<M, L2>(self, f: M) -> SlogScope<L2, F>
+    where
+        M: FnOnce(L) -> L2,
+        L2: Borrow<Logger>,
+    {
+        self.map_parts(|logger, inner| (f(logger), inner))
+    }
+
+    /// Rebuild the wrapper from its transformed parts, keeping any enabled
+    /// diagnostics.
+    pub(crate) fn map_parts<M, L2, G>(mut self, f: M) -> SlogScope<L2, G>
+    where
+        M: FnOnce(L, F) -> (L2, G),
+        L2: Borrow<Logger>,
+    {
+        let diagnostics = self.diagnostics.take();
+        let created = self.created;
+        let (logger, inner) = self.into_parts();
+        let (logger, inner) = f(logger, inner);
+
+        SlogScope {
+            logger: Some(logger),
+            inner: Some(inner),
+            diagnostics,
+            created,
+        }
+    }
+
+    /// Run `f` on the inner value with the logger's scope active.
+    #[cfg(any(feature = "futures-01", feature = "tower"))]
+    fn scoped_mut<R>(&mut self, f: impl FnOnce(&mut F) -> R) -> R {
+        let inner = self.inner.as_mut().expect(TAKEN_APART);
+        let logger = self.logger.as_ref().expect(TAKEN_APART);
+
+        slog_scope::scope(logger.borrow(), || f(inner))
+    }
+
+    /// Run `f` on the pinned inner value with the logger's scope active.
+    #[cfg(any(
+        feature = "futures-03",
+        feature = "http-body",
+        feature = "tokio",
+        all(feature = "nightly-async-iter", nightly),
+    ))]
+    fn scoped<'a, R>(self: Pin<&'a mut Self>, f: impl FnOnce(Pin<&'a mut F>) -> R) -> R {
+        let this = self.project();
+        let inner = this.inner.as_pin_mut().expect(TAKEN_APART);
+        let logger = this.logger.as_ref().expect(TAKEN_APART).borrow();
+
+        slog_scope::scope(logger, || f(inner))
     }
 }
+
+/// Shows the inner value, leaving the logger opaque.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::SlogScope;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!());
+/// let scoped = SlogScope::new(logger, std::future::ready(1));
+///
+/// assert_eq!(
+///     format!("{:?}", scoped),
+///     "SlogScope { logger: Logger { .. }, inner: Ready(Some(1)) }",
+/// );
+/// ```
+impl<L, F> fmt::Debug for SlogScope<L, F>
+where
+    F: fmt::Debug,
+    L: Borrow<Logger>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlogScope")
+            .field("logger", &format_args!("Logger {{ .. }}"))
+            .field("inner", self.get_ref())
+            .finish()
+    }
+}
+
+/// Run a future in a slog scope.
+///
+/// This is the async counterpart of `slog_scope::scope`, as a shorthand for
+/// [`SlogScope::new`]. The logger can be owned or borrowed.
+pub fn scoped<L, F>(logger: L, future: F) -> SlogScope<L, F>
+where
+    L: Borrow<Logger>,
+    F: std::future::Future,
+{
+    SlogScope::new(logger, future)
+}
+
+/// Create a future from a polling closure, called in a slog scope.
+///
+/// This is `std::future::poll_fn` with the scope entered around every call
+/// of `f`. The logger can be owned or borrowed:
+///
+/// ```
+/// # use slog::o;
+/// use std::task::Poll;
+/// use slog_scope_futures::poll_fn_scoped;
+///
+/// let conn = slog::Logger::root(slog::Discard, o!("conn" => 1));
+///
+/// let mut scopes = Vec::new();
+/// let handshake = poll_fn_scoped(&conn, |cx| {
+///     scopes.push(format!("{:?}", slog_scope::logger()));
+///     if scopes.len() < 3 {
+///         cx.waker().wake_by_ref();
+///         return Poll::Pending;
+///     }
+///     Poll::Ready(())
+/// });
+///
+/// futures_executor::block_on(handshake);
+/// assert_eq!(scopes, ["Logger(conn)"; 3]);
+/// ```
+pub fn poll_fn_scoped<L, T, F>(logger: L, f: F) -> SlogScope<L, std::future::PollFn<F>>
+where
+    L: Borrow<Logger>,
+    F: FnMut(&mut std::task::Context<'_>) -> std::task::Poll<T>,
+{
+    SlogScope::new(logger, std::future::poll_fn(f))
+}
+
+#[cfg(feature = "futures-03")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-03")))]
+/// Run a future to completion on the current thread, in a slog scope.
+///
+/// This is `futures::executor::block_on` with the future wrapped in a
+/// [`SlogScope`].
+pub fn block_on_scoped<L, F>(logger: L, future: F) -> F::Output
+where
+    L: Borrow<Logger>,
+    F: std::future::Future,
+{
+    futures_executor::block_on(SlogScope::new(logger, future))
+}
+
+#[cfg(feature = "futures-03")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-03")))]
+/// Run a future to completion on the current thread, in the current scope.
+///
+/// The current logger is captured when this is called, so it also applies to
+/// anything the future hands it on to.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::block_on_in_current_scope;
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("cli" => "sync"));
+///
+/// slog_scope::scope(&logger, || {
+///     let scope = block_on_in_current_scope(async { format!("{:?}", slog_scope::logger()) });
+///     assert_eq!(scope, "Logger(cli)");
+/// });
+/// ```
+pub fn block_on_in_current_scope<F>(future: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    block_on_scoped(slog_scope::logger(), future)
+}
+
+/// Run an async block in a slog scope.
+///
+/// `scope!(logger, { .. })` wraps an `async move` block in a [`SlogScope`],
+/// and `scope!(logger => expr)` does the same for a single expression. The
+/// logger can be owned or borrowed, and is evaluated once, outside the
+/// block. `?` returns from the block, as usual.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::scope;
+///
+/// async fn step() -> Result<String, ()> {
+///     Ok(format!("{:?}", slog_scope::logger()))
+/// }
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("job" => 1));
+///
+/// let out = futures_executor::block_on(scope!(logger.clone(), {
+///     let first = step().await?;
+///     let second = step().await?;
+///     Ok::<_, ()>(first + &second)
+/// }));
+/// assert_eq!(out.unwrap(), "Logger(job)Logger(job)");
+///
+/// let out = futures_executor::block_on(scope!(&logger => step().await));
+/// assert_eq!(out.unwrap(), "Logger(job)");
+/// ```
+///
+/// Anything that isn't a `Logger`, or a reference to one, is rejected:
+///
+/// ```compile_fail
+/// use slog_scope_futures::scope;
+///
+/// let _ = scope!("not a logger", { 1 });
+/// ```
+#[macro_export]
+macro_rules! scope {
+    ($logger:expr, $body:block) => {
+        $crate::SlogScope::new($logger, async move $body)
+    };
+    ($logger:expr => $body:expr) => {
+        $crate::SlogScope::new($logger, async move { $body })
+    };
+}
+
+/// Join futures, each in the scope of its own child of a base logger.
+///
+/// `scoped_join!(logger; "db" => a, "cache" => b)` is `join!(a, b)` with
+/// each branch under `logger.new(o!("branch" => ..))`. A branch can also
+/// give its own values with `o!(..) => c`. The base logger can be owned or
+/// borrowed, and outputs are returned in branch order. Like `join!`, it can
+/// only be used inside async code:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::scoped_join;
+///
+/// async fn fetch() -> String {
+///     format!("{:?}", slog_scope::logger())
+/// }
+///
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// let (db, cache, api) = futures_executor::block_on(async {
+///     scoped_join!(&request;
+///         "db" => fetch(),
+///         "cache" => fetch(),
+///         o!("branch" => "api", "endpoint" => "/users") => fetch(),
+///     )
+/// });
+///
+/// assert_eq!(db, "Logger(branch, request_id)");
+/// assert_eq!(cache, "Logger(branch, request_id)");
+/// assert_eq!(api, "Logger(endpoint, branch, request_id)");
+/// ```
+#[cfg(all(feature = "macros", feature = "futures-03"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "futures-03"))))]
+#[macro_export]
+macro_rules! scoped_join {
+    ($logger:expr; $($branches:tt)+) => {
+        $crate::__scoped_branches!(join, $logger; [] $($branches)+)
+    };
+}
+
+/// Join fallible futures, each in the scope of its own child of a base
+/// logger.
+///
+/// This is [`scoped_join!`] for `try_join!`: it returns the first error, or
+/// all the outputs in branch order.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::scoped_try_join;
+///
+/// async fn fetch(ok: bool) -> Result<String, String> {
+///     let scope = format!("{:?}", slog_scope::logger());
+///     if ok { Ok(scope) } else { Err(scope) }
+/// }
+///
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// futures_executor::block_on(async {
+///     let out = scoped_try_join!(&request; "db" => fetch(true), "cache" => fetch(true));
+///     assert_eq!(out.unwrap().0, "Logger(branch, request_id)");
+///
+///     let out = scoped_try_join!(request; "db" => fetch(true), o!("cache" => 2) => fetch(false));
+///     assert_eq!(out.unwrap_err(), "Logger(cache, request_id)");
+/// });
+/// ```
+#[cfg(all(feature = "macros", feature = "futures-03"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "futures-03"))))]
+#[macro_export]
+macro_rules! scoped_try_join {
+    ($logger:expr; $($branches:tt)+) => {
+        $crate::__scoped_branches!(try_join, $logger; [] $($branches)+)
+    };
+}
+
+/// Wait on several futures at once, each in the scope of its own logger.
+///
+/// This is `select!` with every arm given as `logger, pattern = future =>
+/// body`. The future is polled in the logger's scope, and the body of the
+/// arm that completes first runs in it too. `complete` and `default` arms
+/// work as with `select!`, outside of any added scope. Futures are moved
+/// into their wrapper, so pass the ones kept across loop iterations as
+/// `&mut`, which needs them to be `FusedFuture + Unpin` as usual:
+///
+/// ```
+/// # use slog::o;
+/// use futures_util::{future, FutureExt as _};
+/// use slog_scope_futures::scoped_select;
+///
+/// async fn fetch() -> String {
+///     format!("{:?}", slog_scope::logger())
+/// }
+///
+/// let primary = slog::Logger::root(slog::Discard, o!("primary" => 1));
+/// let replica = slog::Logger::root(slog::Discard, o!("replica" => 1));
+///
+/// let mut answers = Vec::new();
+/// futures_executor::block_on(async {
+///     let mut first = fetch().boxed().fuse();
+///     let mut second = fetch().boxed().fuse();
+///     loop {
+///         let answer = scoped_select! {
+///             &primary, scope = &mut first => (scope, format!("{:?}", slog_scope::logger())),
+///             &replica, scope = &mut second => (scope, format!("{:?}", slog_scope::logger())),
+///             complete => break,
+///         };
+///         answers.push(answer);
+///     }
+/// });
+///
+/// answers.sort();
+/// assert_eq!(
+///     answers,
+///     [
+///         ("Logger(primary)".to_owned(), "Logger(primary)".to_owned()),
+///         ("Logger(replica)".to_owned(), "Logger(replica)".to_owned()),
+///     ],
+/// );
+/// ```
+///
+/// The body of a scoped arm runs in a closure, so unlike with `select!` it
+/// can't `.await`, `break` or `return`. Hand its result back instead.
+#[cfg(all(feature = "macros", feature = "futures-03"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "macros", feature = "futures-03"))))]
+#[macro_export]
+macro_rules! scoped_select {
+    ($($arms:tt)+) => {
+        $crate::__scoped_select!([] $($arms)+)
+    };
+}
+
+// Binds each scoped arm's logger in turn, then hands all the arms to
+// `select!`.
+#[cfg(all(feature = "macros", feature = "futures-03"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scoped_select {
+    ([$($done:tt)*]) => {
+        $crate::__private::select! { $($done)* }
+    };
+    ([$($done:tt)*] $kind:ident => $body:block $(, $($rest:tt)*)?) => {
+        $crate::__scoped_select!([$($done)* $kind => $body,] $($($rest)*)?)
+    };
+    ([$($done:tt)*] $kind:ident => $body:expr $(, $($rest:tt)*)?) => {
+        $crate::__scoped_select!([$($done)* $kind => $body,] $($($rest)*)?)
+    };
+    ([$($done:tt)*] $logger:expr, $pat:pat = $fut:expr => $body:block $(, $($rest:tt)*)?) => {
+        $crate::__scoped_select!([$($done)*] $logger, $pat = $fut => ($body), $($($rest)*)?)
+    };
+    ([$($done:tt)*] $logger:expr, $pat:pat = $fut:expr => $body:expr $(, $($rest:tt)*)?) => {{
+        let logger: &$crate::__private::Logger = &$logger;
+        $crate::__scoped_select!(
+            [$($done)* $pat = $crate::SlogScope::new(logger, $fut) => $crate::__private::scope(logger, || $body),]
+            $($($rest)*)?
+        )
+    }};
+}
+
+// Turns each `name => fut` or `values => fut` branch into `(values) => fut`,
+// then hands them all to `$join`.
+#[cfg(all(feature = "macros", feature = "futures-03"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __scoped_branches {
+    ($join:ident, $logger:expr; [$($done:tt)*] $name:literal => $fut:expr $(, $($rest:tt)*)?) => {
+        $crate::__scoped_branches!(
+            $join, $logger;
+            [$($done)* ($crate::__private::o!("branch" => $name)) => $fut,]
+            $($($rest)*)?
+        )
+    };
+    ($join:ident, $logger:expr; [$($done:tt)*] $values:expr => $fut:expr $(, $($rest:tt)*)?) => {
+        $crate::__scoped_branches!($join, $logger; [$($done)* ($values) => $fut,] $($($rest)*)?)
+    };
+    ($join:ident, $logger:expr; [$(($values:expr) => $fut:expr,)+]) => {{
+        let logger: &$crate::__private::Logger = &$logger;
+        $crate::__private::$join!($($crate::SlogScope::new(logger.new($values), $fut)),+)
+    }};
+}
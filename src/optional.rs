@@ -0,0 +1,92 @@
+//! Futures that are only wrapped in a slog scope if a logger is given, e.g.
+//! for verbose per-request logging behind a flag:
+//!
+//! ```
+//! # use slog::o;
+//! use std::future::Future;
+//! use slog_scope_futures::{optional::OptionalScope, FutureExt};
+//!
+//! async fn handle() -> String {
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! fn request(verbose: bool, logger: &slog::Logger) -> OptionalScope<slog::Logger, impl Future<Output = String>> {
+//!     handle().with_optional_logger(verbose.then(|| logger.new(o!("verbose" => true))))
+//! }
+//!
+//! let server = slog::Logger::root(slog::Discard, o!("server" => 1));
+//!
+//! slog_scope::scope(&server, || {
+//!     assert_eq!(futures_executor::block_on(request(true, &server)), "Logger(verbose, server)");
+//!
+//!     // Without a logger, the scope isn't touched at all.
+//!     assert_eq!(futures_executor::block_on(request(false, &server)), "Logger(server)");
+//! });
+//! ```
+
+use std::{
+    borrow::Borrow,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use slog::Logger;
+
+use super::SlogScope;
+
+pin_project! {
+    /// A `Future` wrapped in a slog scope, or not.
+    ///
+    /// Created by [`FutureExt::with_optional_logger`](crate::std_future::FutureExt::with_optional_logger).
+    pub struct OptionalScope<L, F>
+    where
+        L: Borrow<Logger>,
+    {
+        #[pin]
+        inner: Inner<L, F>,
+    }
+}
+
+pin_project! {
+    #[project = InnerProj]
+    enum Inner<L, F>
+    where
+        L: Borrow<Logger>,
+    {
+        Scoped { #[pin] future: SlogScope<L, F> },
+        Unscoped { #[pin] future: F },
+    }
+}
+
+impl<L, F> OptionalScope<L, F>
+where
+    L: Borrow<Logger>,
+{
+    pub(crate) fn new(logger: Option<L>, future: F) -> Self {
+        let inner = match logger {
+            Some(logger) => Inner::Scoped {
+                future: SlogScope::new(logger, future),
+            },
+            None => Inner::Unscoped { future },
+        };
+
+        OptionalScope { inner }
+    }
+}
+
+impl<L, F> Future for OptionalScope<L, F>
+where
+    F: Future,
+    L: Borrow<Logger>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            InnerProj::Scoped { future } => future.poll(cx),
+            InnerProj::Unscoped { future } => future.poll(cx),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Futures logging their outcome in the scope they complete in, saving the
+//! usual `if let Err(e) = &res { error!(..) }` after every `.await`:
+//!
+//! ```
+//! # use slog::o;
+//! use slog::Level;
+//! use slog_scope_futures::{test_util::capture, FutureExt};
+//!
+//! async fn parse(input: &str) -> Result<u32, std::num::ParseIntError> {
+//!     input.parse()
+//! }
+//!
+//! let (logger, records) = capture();
+//! let request = logger.new(o!("request_id" => 1));
+//!
+//! futures_executor::block_on(async {
+//!     let ok = parse("1").log_err(Level::Error).with_logger(&request);
+//!     assert_eq!(ok.await, Ok(1));
+//!     assert!(records.is_empty());
+//!
+//!     let err = parse("one").log_err(Level::Error).with_logger(&request);
+//!     assert!(err.await.is_err());
+//! });
+//!
+//! let records = records.records();
+//! assert_eq!(records.len(), 1);
+//! assert_eq!(records[0].level(), Level::Error);
+//! assert_eq!(records[0].msg(), "invalid digit found in string");
+//! assert_eq!(records[0].kv("err"), Some("ParseIntError { kind: InvalidDigit }"));
+//! assert_eq!(records[0].kv("request_id"), Some("1"));
+//! ```
+//!
+//! The record is logged in whatever scope is active when the future
+//! completes, so these go inside of `with_logger`, not outside.
+
+use std::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Instant,
+};
+
+use pin_project_lite::pin_project;
+use slog::{o, Level, OwnedKV, SendSyncRefUnwindSafeKV};
+
+use super::instrument::log_at;
+
+pin_project! {
+    /// A `Future` logging the error it resolves to, if any.
+    ///
+    /// Created by [`FutureExt::log_err`](crate::std_future::FutureExt::log_err).
+    #[derive(Clone, Debug)]
+    pub struct LogErr<F> {
+        #[pin]
+        inner: F,
+        level: Level,
+    }
+}
+
+impl<F> LogErr<F> {
+    pub(crate) fn new(inner: F, level: Level) -> Self {
+        LogErr { inner, level }
+    }
+}
+
+impl<F, T, E> Future for LogErr<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: fmt::Display + fmt::Debug,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let out = ready!(this.inner.poll(cx));
+
+        if let Err(e) = &out {
+            let level = *this.level;
+            slog_scope::with_logger(|logger| log_at!(logger, level, "{}", e; "err" => ?e));
+        }
+
+        Poll::Ready(out)
+    }
+}
+
+/// Outputs of futures that [`LogOutcome`] can tell successes from failures
+/// of.
+///
+/// Implemented for `Result`s, whose errors are failures, and for `()`, which
+/// always succeeds. Other outputs can be logged by implementing it, or with
+/// [`LogSuccess`], which counts every output as a success.
+pub trait Outcome {
+    /// The type of failures.
+    type Error: fmt::Display + fmt::Debug + ?Sized;
+
+    /// Get the failure, if any.
+    fn error(&self) -> Option<&Self::Error>;
+}
+
+impl<T, E> Outcome for Result<T, E>
+where
+    E: fmt::Display + fmt::Debug,
+{
+    type Error = E;
+
+    fn error(&self) -> Option<&E> {
+        self.as_ref().err()
+    }
+}
+
+impl Outcome for () {
+    type Error = Infallible;
+
+    fn error(&self) -> Option<&Infallible> {
+        None
+    }
+}
+
+pin_project! {
+    /// A `Future` logging its outcome when it completes.
+    ///
+    /// Created by [`FutureExt::log_outcome`](crate::std_future::FutureExt::log_outcome).
+    /// Successes are logged as `"<op> succeeded"`, at the success level.
+    /// Failures are logged as `"<op> failed"`, at the failure level, with
+    /// the error under an `error` KV. Both carry an `elapsed_ms` KV counted
+    /// from the first poll, along with any values given to
+    /// [`with_values`](Self::with_values):
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog::Level::{Debug, Warning};
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// async fn query(id: u32) -> Result<String, String> {
+    ///     if id == 1 { Ok("alice".to_owned()) } else { Err(format!("no user {}", id)) }
+    /// }
+    ///
+    /// async fn flush() {}
+    ///
+    /// let (logger, records) = capture();
+    /// let request = logger.new(o!("request_id" => 1));
+    ///
+    /// let handle = async {
+    ///     let user = query(1).log_outcome("db query", Debug, Warning).await;
+    ///     let missing = query(2)
+    ///         .log_outcome("db query", Debug, Warning)
+    ///         .with_values(o!("table" => "users"))
+    ///         .with_kv(o!("step" => "lookup"))
+    ///         .await;
+    ///     flush().log_outcome("flush", Debug, Warning).await;
+    ///     (user, missing)
+    /// };
+    ///
+    /// let (user, missing) = futures_executor::block_on(handle.with_logger(request));
+    /// assert_eq!((user.unwrap(), missing.unwrap_err()), ("alice".to_owned(), "no user 2".to_owned()));
+    ///
+    /// let records = records.records();
+    /// let logged: Vec<_> = records.iter().map(|r| (r.level(), r.msg(), r.kv("error"))).collect();
+    /// assert_eq!(
+    ///     logged,
+    ///     [
+    ///         (Debug, "db query succeeded", None),
+    ///         (Warning, "db query failed", Some("no user 2")),
+    ///         (Debug, "flush succeeded", None),
+    ///     ],
+    /// );
+    /// assert!(records.iter().all(|r| r.kv("request_id") == Some("1") && r.kv("elapsed_ms").is_some()));
+    /// assert_eq!((records[1].kv("table"), records[1].kv("step")), (Some("users"), Some("lookup")));
+    /// ```
+    ///
+    /// Futures with other outputs can be logged with
+    /// [`log_success`](crate::std_future::FutureExt::log_success) instead.
+    pub struct LogOutcome<F, T>
+    where
+        T: SendSyncRefUnwindSafeKV,
+    {
+        #[pin]
+        inner: F,
+        op: &'static str,
+        success: Level,
+        failure: Level,
+        values: OwnedKV<T>,
+        started: Option<Instant>,
+    }
+}
+
+impl<F> LogOutcome<F, ()> {
+    pub(crate) fn new(inner: F, op: &'static str, success: Level, failure: Level) -> Self {
+        LogOutcome {
+            inner,
+            op,
+            success,
+            failure,
+            values: o!(),
+            started: None,
+        }
+    }
+}
+
+impl<F, T> LogOutcome<F, T>
+where
+    T: SendSyncRefUnwindSafeKV,
+{
+    /// Add `values` to the records logged, replacing any given before.
+    pub fn with_values<U>(self, values: OwnedKV<U>) -> LogOutcome<F, U>
+    where
+        U: SendSyncRefUnwindSafeKV,
+    {
+        LogOutcome {
+            inner: self.inner,
+            op: self.op,
+            success: self.success,
+            failure: self.failure,
+            values,
+            started: self.started,
+        }
+    }
+}
+
+impl<F, T> Future for LogOutcome<F, T>
+where
+    F: Future,
+    F::Output: Outcome,
+    T: SendSyncRefUnwindSafeKV,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let started = *this.started.get_or_insert_with(Instant::now);
+        let out = ready!(this.inner.poll(cx));
+
+        let elapsed_ms = started.elapsed().as_millis();
+        let (op, success, failure) = (*this.op, *this.success, *this.failure);
+        let values = &this.values.0;
+        slog_scope::with_logger(|logger| match out.error() {
+            Some(e) => {
+                log_at!(logger, failure, "{} failed", op; "error" => %e, "elapsed_ms" => elapsed_ms, values)
+            }
+            None => {
+                log_at!(logger, success, "{} succeeded", op; "elapsed_ms" => elapsed_ms, values)
+            }
+        });
+
+        Poll::Ready(out)
+    }
+}
+
+pin_project! {
+    /// A `Future` logging its completion as a success, whatever its output.
+    ///
+    /// Created by [`FutureExt::log_success`](crate::std_future::FutureExt::log_success).
+    /// This is [`LogOutcome`] for futures whose output isn't an [`Outcome`]:
+    /// the record is `"<op> succeeded"`, at the given level, with the same
+    /// `elapsed_ms` KV and values:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let request = logger.new(o!("request_id" => 1));
+    ///
+    /// let count = async { 42 }
+    ///     .log_success("count", Level::Debug)
+    ///     .with_values(o!("table" => "users"))
+    ///     .with_logger(request);
+    /// assert_eq!(futures_executor::block_on(count), 42);
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].level(), Level::Debug);
+    /// assert_eq!(records[0].msg(), "count succeeded");
+    /// assert_eq!(records[0].kv("table"), Some("users"));
+    /// assert_eq!(records[0].kv("request_id"), Some("1"));
+    /// assert!(records[0].kv("elapsed_ms").is_some());
+    /// ```
+    pub struct LogSuccess<F, T>
+    where
+        T: SendSyncRefUnwindSafeKV,
+    {
+        #[pin]
+        inner: F,
+        op: &'static str,
+        level: Level,
+        values: OwnedKV<T>,
+        started: Option<Instant>,
+    }
+}
+
+impl<F> LogSuccess<F, ()> {
+    pub(crate) fn new(inner: F, op: &'static str, level: Level) -> Self {
+        LogSuccess {
+            inner,
+            op,
+            level,
+            values: o!(),
+            started: None,
+        }
+    }
+}
+
+impl<F, T> LogSuccess<F, T>
+where
+    T: SendSyncRefUnwindSafeKV,
+{
+    /// Add `values` to the record logged, replacing any given before.
+    pub fn with_values<U>(self, values: OwnedKV<U>) -> LogSuccess<F, U>
+    where
+        U: SendSyncRefUnwindSafeKV,
+    {
+        LogSuccess {
+            inner: self.inner,
+            op: self.op,
+            level: self.level,
+            values,
+            started: self.started,
+        }
+    }
+}
+
+impl<F, T> Future for LogSuccess<F, T>
+where
+    F: Future,
+    T: SendSyncRefUnwindSafeKV,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let started = *this.started.get_or_insert_with(Instant::now);
+        let out = ready!(this.inner.poll(cx));
+
+        let elapsed_ms = started.elapsed().as_millis();
+        let (op, level) = (*this.op, *this.level);
+        let values = &this.values.0;
+        slog_scope::with_logger(
+            |logger| log_at!(logger, level, "{} succeeded", op; "elapsed_ms" => elapsed_ms, values),
+        );
+
+        Poll::Ready(out)
+    }
+}
@@ -0,0 +1,254 @@
+//! Closures handed to rayon run on its worker threads, away from the scope
+//! they were set up in. [`spawn_scoped`] and
+//! [`ThreadPoolExt::install_scoped`] carry the logger along for a single
+//! closure, and [`ParallelIteratorExt`] extends it to every worker taking part
+//! in a parallel iterator:
+//!
+//! ```
+//! # use slog::o;
+//! use std::sync::Mutex;
+//! use rayon::prelude::*;
+//! use slog_scope_futures::rayon::{ParallelIteratorExt, ThreadPoolExt};
+//!
+//! let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let records = Mutex::new(Vec::new());
+//!
+//! pool.install_scoped(&request, || {
+//!     (0..1000).into_par_iter().with_current_logger().for_each(|_| {
+//!         records.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+//!     })
+//! });
+//!
+//! let records = records.into_inner().unwrap();
+//! assert_eq!(records.len(), 1000);
+//! assert!(records.iter().all(|r| r == "Logger(request_id)"));
+//! ```
+//!
+//! [`spawn_scoped`]: crate::rayon::spawn_scoped
+//! [`ThreadPoolExt::install_scoped`]: crate::rayon::ThreadPoolExt::install_scoped
+//! [`ParallelIteratorExt`]: crate::rayon::ParallelIteratorExt
+
+use std::borrow::Borrow;
+
+use rayon::{
+    iter::{
+        plumbing::{Consumer, Folder, UnindexedConsumer},
+        ParallelIterator,
+    },
+    ThreadPool,
+};
+use slog::Logger;
+
+use super::closure::ScopedFnOnce;
+
+/// Run a closure on rayon's global pool in the current scope.
+///
+/// The current logger is captured when this is called.
+///
+/// ```
+/// # use slog::o;
+/// use std::sync::mpsc;
+/// use slog_scope_futures::rayon::spawn_scoped;
+///
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+/// let (tx, rx) = mpsc::channel();
+///
+/// slog_scope::scope(&request, || {
+///     spawn_scoped(move || tx.send(format!("{:?}", slog_scope::logger())).unwrap())
+/// });
+/// assert_eq!(rx.recv().unwrap(), "Logger(request_id)");
+/// ```
+pub fn spawn_scoped<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    spawn_scoped_with(slog_scope::logger(), f)
+}
+
+/// Run a closure on rayon's global pool in `logger`'s scope.
+pub fn spawn_scoped_with<F>(logger: Logger, f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    rayon::spawn(ScopedFnOnce::new(logger, f).into_fn())
+}
+
+/// Scoped execution for `rayon::ThreadPool`.
+pub trait ThreadPoolExt {
+    /// Run `f` in the pool, in `logger`'s scope.
+    ///
+    /// Only `f` itself is scoped. Work it splits off to other workers, e.g.
+    /// through parallel iterators, runs in whatever scope those workers are
+    /// in; use [`ParallelIteratorExt`] to scope that too.
+    fn install_scoped<L, F, R>(&self, logger: L, f: F) -> R
+    where
+        L: Borrow<Logger> + Send,
+        F: FnOnce() -> R + Send,
+        R: Send;
+
+    /// Run a closure in the pool in the current scope, without waiting for
+    /// it.
+    ///
+    /// The current logger is captured when this is called.
+    fn spawn_scoped<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static;
+}
+
+impl ThreadPoolExt for ThreadPool {
+    fn install_scoped<L, F, R>(&self, logger: L, f: F) -> R
+    where
+        L: Borrow<Logger> + Send,
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        self.install(ScopedFnOnce::new(logger, f).into_fn())
+    }
+
+    fn spawn_scoped<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.spawn(ScopedFnOnce::new(slog_scope::logger(), f).into_fn())
+    }
+}
+
+/// Convenience trait for scoping the rest of a parallel iterator chain.
+///
+/// Every adapter and consumer after the call runs in the logger's scope, on
+/// whichever worker thread picks up the work. Automatically implemented for
+/// all `ParallelIterator`s.
+pub trait ParallelIteratorExt: ParallelIterator {
+    /// Run the rest of the chain in `logger`'s scope.
+    fn with_logger(self, logger: Logger) -> ScopedParIter<Self> {
+        ScopedParIter { base: self, logger }
+    }
+
+    /// Run the rest of the chain in the current scope.
+    ///
+    /// The current logger is captured when this is called.
+    fn with_current_logger(self) -> ScopedParIter<Self> {
+        self.with_logger(slog_scope::logger())
+    }
+}
+
+impl<I> ParallelIteratorExt for I where I: ParallelIterator {}
+
+/// A `ParallelIterator` whose consumers run in a slog scope.
+///
+/// Created by [`ParallelIteratorExt::with_logger`].
+#[derive(Clone, Debug)]
+pub struct ScopedParIter<I> {
+    base: I,
+    logger: Logger,
+}
+
+impl<I> ParallelIterator for ScopedParIter<I>
+where
+    I: ParallelIterator,
+{
+    type Item = I::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let consumer = ScopedConsumer {
+            base: consumer,
+            logger: self.logger,
+        };
+
+        self.base.drive_unindexed(consumer)
+    }
+}
+
+struct ScopedConsumer<C> {
+    base: C,
+    logger: Logger,
+}
+
+impl<T, C> Consumer<T> for ScopedConsumer<C>
+where
+    C: Consumer<T>,
+{
+    type Folder = ScopedConsumer<C::Folder>;
+    type Reducer = C::Reducer;
+    type Result = C::Result;
+
+    fn split_at(self, index: usize) -> (Self, Self, Self::Reducer) {
+        let (left, right, reducer) = self.base.split_at(index);
+        let left = ScopedConsumer {
+            base: left,
+            logger: self.logger.clone(),
+        };
+        let right = ScopedConsumer {
+            base: right,
+            logger: self.logger,
+        };
+
+        (left, right, reducer)
+    }
+
+    fn into_folder(self) -> Self::Folder {
+        ScopedConsumer {
+            base: self.base.into_folder(),
+            logger: self.logger,
+        }
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
+
+impl<T, C> UnindexedConsumer<T> for ScopedConsumer<C>
+where
+    C: UnindexedConsumer<T>,
+{
+    fn split_off_left(&self) -> Self {
+        ScopedConsumer {
+            base: self.base.split_off_left(),
+            logger: self.logger.clone(),
+        }
+    }
+
+    fn to_reducer(&self) -> Self::Reducer {
+        self.base.to_reducer()
+    }
+}
+
+impl<T, F> Folder<T> for ScopedConsumer<F>
+where
+    F: Folder<T>,
+{
+    type Result = F::Result;
+
+    fn consume(self, item: T) -> Self {
+        let ScopedConsumer { base, logger } = self;
+        let base = slog_scope::scope(&logger, || base.consume(item));
+
+        ScopedConsumer { base, logger }
+    }
+
+    fn consume_iter<I>(self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Enter the scope once per batch rather than once per item.
+        let ScopedConsumer { base, logger } = self;
+        let base = slog_scope::scope(&logger, || base.consume_iter(iter));
+
+        ScopedConsumer { base, logger }
+    }
+
+    fn complete(self) -> Self::Result {
+        let ScopedConsumer { base, logger } = self;
+
+        slog_scope::scope(&logger, || base.complete())
+    }
+
+    fn full(&self) -> bool {
+        self.base.full()
+    }
+}
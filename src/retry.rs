@@ -0,0 +1,164 @@
+//! Retry loops where every attempt runs in its own child scope, with an
+//! `attempt` KV:
+//!
+//! ```
+//! # use slog::o;
+//! use std::{cell::RefCell, time::Duration};
+//! use slog::Level;
+//! use slog_scope_futures::{
+//!     retry::{retry_scoped, RetryPolicy},
+//!     test_util::capture,
+//! };
+//!
+//! let (logger, records) = capture();
+//! let request = logger.new(o!("request_id" => 1));
+//!
+//! let scopes = RefCell::new(Vec::new());
+//! let connect = || async {
+//!     scopes.borrow_mut().push(format!("{:?}", slog_scope::logger()));
+//!     if scopes.borrow().len() < 3 { Err("refused") } else { Ok("connected") }
+//! };
+//!
+//! // Any runtime's sleep works.
+//! let policy = RetryPolicy::new(5, |_| async {}).with_backoff(Duration::from_millis(10), Duration::from_secs(1));
+//! let out = slog_scope::scope(&request, || retry_scoped(connect, policy));
+//!
+//! assert_eq!(futures_executor::block_on(out), Ok("connected"));
+//! assert_eq!(*scopes.borrow(), ["Logger(attempt, request_id)"; 3]);
+//!
+//! let records = records.records();
+//! assert!(records.iter().all(|r| r.level() == Level::Warning && r.msg() == "attempt failed"));
+//! assert!(records.iter().all(|r| r.kv("error") == Some("refused") && r.kv("request_id") == Some("1")));
+//!
+//! let attempts: Vec<_> = records.iter().map(|r| (r.kv("attempt"), r.kv("backoff_ms"))).collect();
+//! assert_eq!(attempts, [(Some("1"), Some("10")), (Some("2"), Some("20"))]);
+//! ```
+
+use std::{fmt, future::Future, time::Duration};
+
+use slog::{error, o, warn, Logger};
+
+use super::SlogScope;
+
+/// How many times to attempt an operation, and how long to wait in between.
+///
+/// Waiting is done through a sleep function, e.g. `tokio::time::sleep`, so
+/// any runtime can be used.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy<S> {
+    max_attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    sleep: S,
+}
+
+impl<S, Z> RetryPolicy<S>
+where
+    S: FnMut(Duration) -> Z,
+    Z: Future<Output = ()>,
+{
+    /// Make up to `max_attempts` attempts, sleeping with `sleep` in between.
+    ///
+    /// There's no backoff by default.
+    pub fn new(max_attempts: u32, sleep: S) -> Self {
+        RetryPolicy {
+            max_attempts,
+            backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            sleep,
+        }
+    }
+
+    /// Wait `initial` after the first failure, doubling after each following
+    /// one, up to `max`.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial.min(max);
+        self.max_backoff = max;
+        self
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+impl RetryPolicy<fn(Duration) -> tokio::time::Sleep> {
+    /// Make up to `max_attempts` attempts, sleeping with `tokio::time::sleep`
+    /// in between.
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::time::Duration;
+    /// use slog_scope_futures::retry::{retry_scoped_with, RetryPolicy};
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("job" => "sync"));
+    /// let policy = RetryPolicy::tokio(3).with_backoff(Duration::from_secs(1), Duration::from_secs(10));
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread()
+    ///     .enable_time()
+    ///     .start_paused(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let out = runtime.block_on(retry_scoped_with(logger, || async { Err::<(), _>("down") }, policy));
+    /// assert_eq!(out, Err("down"));
+    /// ```
+    pub fn tokio(max_attempts: u32) -> Self {
+        RetryPolicy::new(max_attempts, tokio::time::sleep)
+    }
+}
+
+/// Retry an operation in the current scope, following `policy`.
+///
+/// The current logger is captured when this is called. Every attempt is
+/// created and polled under a child logger with an `attempt` KV, counting
+/// from 1. Failed attempts are logged as warnings, with the error and the
+/// time until the next attempt, and the final failure as an error.
+pub fn retry_scoped<M, F, T, E, S, Z>(
+    make_fut: M,
+    policy: RetryPolicy<S>,
+) -> impl Future<Output = Result<T, E>>
+where
+    M: FnMut() -> F,
+    F: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+    S: FnMut(Duration) -> Z,
+    Z: Future<Output = ()>,
+{
+    retry_scoped_with(slog_scope::logger(), make_fut, policy)
+}
+
+/// Retry an operation in `logger`'s scope, following `policy`.
+///
+/// See [`retry_scoped`].
+pub async fn retry_scoped_with<M, F, T, E, S, Z>(
+    logger: Logger,
+    mut make_fut: M,
+    mut policy: RetryPolicy<S>,
+) -> Result<T, E>
+where
+    M: FnMut() -> F,
+    F: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+    S: FnMut(Duration) -> Z,
+    Z: Future<Output = ()>,
+{
+    let mut backoff = policy.backoff;
+    let mut attempt = 1;
+
+    loop {
+        let attempt_logger = logger.new(o!("attempt" => attempt));
+        let fut = slog_scope::scope(&attempt_logger, &mut make_fut);
+
+        match SlogScope::new(&attempt_logger, fut).await {
+            Ok(out) => return Ok(out),
+            Err(e) if attempt >= policy.max_attempts => {
+                error!(attempt_logger, "all attempts failed"; "error" => %e);
+                return Err(e);
+            }
+            Err(e) => {
+                warn!(attempt_logger, "attempt failed"; "error" => %e, "backoff_ms" => backoff.as_millis());
+                (policy.sleep)(backoff).await;
+                backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+                attempt += 1;
+            }
+        }
+    }
+}
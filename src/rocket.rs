@@ -0,0 +1,179 @@
+//! Rocket owns the futures of its handlers, so scoping requests takes two
+//! parts: [`ScopeFairing`] derives a logger for each request, and
+//! [`scope_routes`] runs the handlers of routes under it. Handlers can log
+//! through `slog_scope` as usual, or grab the request's logger with the
+//! [`ScopedLogger`] guard.
+//!
+//! ```
+//! # use slog::o;
+//! use rocket::{get, local::asynchronous::Client, routes};
+//! use slog_scope_futures::rocket::{scope_routes, ScopeFairing, ScopedLogger};
+//!
+//! #[get("/")]
+//! async fn index(logger: ScopedLogger) -> String {
+//!     assert_eq!(format!("{:?}", logger.0), format!("{:?}", slog_scope::logger()));
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! let root = slog::Logger::root(slog::Discard, o!());
+//! let rocket = rocket::build()
+//!     .attach(ScopeFairing::new(root))
+//!     .mount("/", scope_routes(routes![index]));
+//!
+//! rocket::execute(async {
+//!     let client = Client::untracked(rocket).await.unwrap();
+//!     let res = client.get("/").header(rocket::http::Header::new("x-request-id", "abc")).dispatch().await;
+//!
+//!     assert_eq!(res.headers().get_one("x-request-id"), Some("abc"));
+//!     assert_eq!(res.into_string().await.unwrap(), "Logger(request_id, uri, method)");
+//! });
+//! ```
+//!
+//! [`ScopeFairing`]: crate::rocket::ScopeFairing
+//! [`scope_routes`]: crate::rocket::scope_routes
+//! [`ScopedLogger`]: crate::rocket::ScopedLogger
+
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use rocket::{
+    async_trait,
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    request::{FromRequest, Outcome},
+    route::{self, Handler},
+    Data, Request, Response, Route,
+};
+use slog::{o, Logger};
+
+use super::SlogScope;
+
+/// Header a request id is taken from, if present.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// What the fairing caches for each request.
+struct RequestScope {
+    logger: Logger,
+    request_id: String,
+}
+
+/// A `Fairing` deriving a logger for each request.
+///
+/// The child logger carries `method`, `uri` and `request_id` KVs, the latter
+/// taken from the `x-request-id` header or generated if the header is
+/// missing. It's stored in the request-local cache, where [`scope_routes`],
+/// [`scoped`] and [`ScopedLogger`] find it, and the request id is sent back
+/// in the `x-request-id` header of the response.
+#[derive(Clone, Debug)]
+pub struct ScopeFairing {
+    logger: Logger,
+}
+
+impl ScopeFairing {
+    /// Create a fairing deriving request loggers from `logger`.
+    pub fn new(logger: Logger) -> Self {
+        ScopeFairing { logger }
+    }
+}
+
+#[async_trait]
+impl Fairing for ScopeFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "slog scope",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        let request_id = match req.headers().get_one(REQUEST_ID_HEADER) {
+            Some(id) => id.to_owned(),
+            None => next_request_id().to_string(),
+        };
+        let logger = self.logger.new(o!(
+            "method" => req.method().as_str(),
+            "uri" => req.uri().to_string(),
+            "request_id" => request_id.clone(),
+        ));
+
+        req.local_cache(|| Some(RequestScope { logger, request_id }));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if let Some(scope) = req.local_cache(|| None::<RequestScope>) {
+            res.set_header(Header::new(REQUEST_ID_HEADER, scope.request_id.clone()));
+        }
+    }
+}
+
+fn next_request_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Get the logger of a request.
+///
+/// This is the logger set up by [`ScopeFairing`], falling back to the current
+/// scope's logger if the fairing isn't attached.
+fn request_logger(req: &Request<'_>) -> Logger {
+    match req.local_cache(|| None::<RequestScope>) {
+        Some(scope) => scope.logger.clone(),
+        None => slog_scope::logger(),
+    }
+}
+
+/// Run `future` under the logger of `req`.
+///
+/// Without [`ScopeFairing`], the current logger is captured when this is
+/// called.
+///
+/// For custom `Handler`s and anything else given the request. Routes
+/// generated by Rocket's attributes can be scoped whole with
+/// [`scope_routes`].
+pub fn scoped<F: Future>(req: &Request<'_>, future: F) -> SlogScope<Logger, F> {
+    SlogScope::new(request_logger(req), future)
+}
+
+/// Run the handlers of `routes` under the logger of each request.
+///
+/// The logger is the one set up by [`ScopeFairing`], or the current scope's
+/// logger if the fairing isn't attached.
+pub fn scope_routes(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            route.handler = Box::new(ScopedHandler(route.handler));
+            route
+        })
+        .collect()
+}
+
+/// A `Handler` running another one under the logger of each request.
+#[derive(Clone)]
+struct ScopedHandler(Box<dyn Handler>);
+
+#[async_trait]
+impl Handler for ScopedHandler {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> route::Outcome<'r> {
+        scoped(req, self.0.handle(req, data)).await
+    }
+}
+
+/// Request guard for the logger of the current request.
+///
+/// This is the logger set up by [`ScopeFairing`], falling back to the current
+/// scope's logger if the fairing isn't attached.
+#[derive(Clone, Debug)]
+pub struct ScopedLogger(pub Logger);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for ScopedLogger {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ScopedLogger(request_logger(req)))
+    }
+}
@@ -0,0 +1,105 @@
+//! A logger handle for fanning out to many tasks.
+//!
+//! Which logger to give a wrapper depends on where the future goes:
+//!
+//! | Logger          | Cost per wrapper  | `'static` | Use for                            |
+//! |-----------------|-------------------|-----------|------------------------------------|
+//! | `&Logger`       | none              | no        | futures awaited in place           |
+//! | `Logger`        | a `Logger` clone  | yes       | a task or two, or a child logger   |
+//! | [`SharedLogger`]| one pointer bump  | yes       | many tasks sharing the same logger |
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::{shared::SharedLogger, FutureExt};
+//!
+//! async fn sub_request() -> String {
+//!     format!("{:?}", slog_scope::logger())
+//! }
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let child = request.new(o!("step" => "auth"));
+//! let shared = SharedLogger::from(&request);
+//!
+//! let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+//! runtime.block_on(async {
+//!     // Borrowed: awaited right here.
+//!     assert_eq!(sub_request().with_logger(&request).await, "Logger(request_id)");
+//!
+//!     // Owned: a one-off task under its own child logger.
+//!     let task = tokio::spawn(sub_request().with_logger(child));
+//!     assert_eq!(task.await.unwrap(), "Logger(step, request_id)");
+//!
+//!     // Shared: the same logger for many tasks.
+//!     let tasks: Vec<_> = (0..8)
+//!         .map(|_| tokio::spawn(sub_request().with_shared_logger(&shared)))
+//!         .collect();
+//!     for task in tasks {
+//!         assert_eq!(task.await.unwrap(), "Logger(request_id)");
+//!     }
+//! });
+//! ```
+//!
+//! [`SharedLogger`]: crate::shared::SharedLogger
+
+use std::{borrow::Borrow, ops::Deref, sync::Arc};
+
+use slog::Logger;
+
+/// A `Logger` behind an `Arc`, cloned with a single pointer bump.
+///
+/// It can be used anywhere an `L: Borrow<Logger>` is expected, e.g. with
+/// [`FutureExt::with_logger`](crate::std_future::FutureExt::with_logger),
+/// or through
+/// [`FutureExt::with_shared_logger`](crate::std_future::FutureExt::with_shared_logger).
+#[derive(Clone, Debug)]
+pub struct SharedLogger(Arc<Logger>);
+
+impl SharedLogger {
+    /// Share `logger`.
+    pub fn new(logger: Logger) -> Self {
+        SharedLogger(Arc::new(logger))
+    }
+
+    /// Share the current logger.
+    pub fn current() -> Self {
+        SharedLogger::new(slog_scope::logger())
+    }
+}
+
+impl Borrow<Logger> for SharedLogger {
+    fn borrow(&self) -> &Logger {
+        &self.0
+    }
+}
+
+impl AsRef<Logger> for SharedLogger {
+    fn as_ref(&self) -> &Logger {
+        &self.0
+    }
+}
+
+impl Deref for SharedLogger {
+    type Target = Logger;
+
+    fn deref(&self) -> &Logger {
+        &self.0
+    }
+}
+
+impl From<Logger> for SharedLogger {
+    fn from(logger: Logger) -> Self {
+        SharedLogger::new(logger)
+    }
+}
+
+impl From<&Logger> for SharedLogger {
+    fn from(logger: &Logger) -> Self {
+        SharedLogger::new(logger.clone())
+    }
+}
+
+impl From<Arc<Logger>> for SharedLogger {
+    fn from(logger: Arc<Logger>) -> Self {
+        SharedLogger(logger)
+    }
+}
@@ -0,0 +1,295 @@
+//! The `std::future::Future` implementation for [`SlogScope`], and
+//! [`FutureExt`] for wrapping futures via method chaining.
+//!
+//! None of this depends on the `futures` crates, so it's all available with
+//! default features disabled:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//! let fut = async { format!("{:?}", slog_scope::logger()) }.with_logger(logger);
+//!
+//! # let out = futures_executor::block_on(fut);
+//! # assert_eq!(out, "Logger(request_id)");
+//! ```
+//!
+//! [`SlogScope`]: crate::SlogScope
+//! [`FutureExt`]: crate::std_future::FutureExt
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use slog::{Level, Logger, OwnedKV, SendSyncRefUnwindSafeKV};
+
+use super::{
+    lazy::{InheritKv, WithKv, WithLoggerFn},
+    optional::OptionalScope,
+    outcome::{LogErr, LogOutcome, LogSuccess, Outcome},
+    shared::SharedLogger,
+    SlogScope,
+};
+
+impl<L, F> Future for SlogScope<L, F>
+where
+    F: Future,
+    L: Borrow<Logger>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_instrumented(cx)
+    }
+}
+
+/// Convenience trait for wrapping a `std::future` in a slog scope via method chaining.
+///
+/// Automatically implemented for all `std::future`s.
+pub trait FutureExt: Future + Sized {
+    /// Wrap `self` in a slog scope
+    fn with_logger<L>(self, logger: L) -> SlogScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        SlogScope::new(logger, self)
+    }
+
+    /// Wrap `self` in a slog scope and box it
+    ///
+    /// The result is the same type as `futures`' `BoxFuture<'a, _>`, for
+    /// storing handlers as trait objects. The wrapper holds a `Logger` of
+    /// its own, cloned from `logger`, so a borrowed logger doesn't tie the
+    /// future to its lifetime:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::collections::HashMap;
+    /// use futures_util::future::BoxFuture;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// async fn list_users() -> String {
+    ///     format!("users {:?}", slog_scope::logger())
+    /// }
+    ///
+    /// async fn health() -> String {
+    ///     "ok".to_owned()
+    /// }
+    ///
+    /// type Handler = fn(&slog::Logger) -> BoxFuture<'static, String>;
+    ///
+    /// let mut routes: HashMap<&str, Handler> = HashMap::new();
+    /// routes.insert("/users", |logger| list_users().with_logger_boxed(logger));
+    /// routes.insert("/health", |logger| health().with_logger_boxed(logger));
+    ///
+    /// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    /// let response = routes["/users"](&request);
+    /// drop(request);
+    ///
+    /// assert_eq!(futures_executor::block_on(response), "users Logger(request_id)");
+    /// ```
+    ///
+    /// Futures that aren't `Send` need
+    /// [`with_logger_boxed_local`](Self::with_logger_boxed_local) instead:
+    ///
+    /// ```compile_fail
+    /// # use slog::o;
+    /// use std::rc::Rc;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!());
+    /// let shared = Rc::new(1);
+    ///
+    /// let _ = async move { *shared }.with_logger_boxed(&logger);
+    /// ```
+    fn with_logger_boxed<'a, L>(
+        self,
+        logger: L,
+    ) -> Pin<Box<dyn Future<Output = Self::Output> + Send + 'a>>
+    where
+        Self: Send + 'a,
+        L: Borrow<Logger>,
+    {
+        Box::pin(SlogScope::new(logger.borrow().clone(), self))
+    }
+
+    /// Wrap `self` in a slog scope and box it, without requiring `Send`
+    ///
+    /// The result is the same type as `futures`' `LocalBoxFuture<'a, _>`.
+    /// As with [`with_logger_boxed`](Self::with_logger_boxed), the logger is
+    /// cloned:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::rc::Rc;
+    /// use futures_util::future::LocalBoxFuture;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// let logger = slog::Logger::root(slog::Discard, o!("window" => 1));
+    /// let title = Rc::new("main");
+    ///
+    /// let handler: LocalBoxFuture<'static, String> =
+    ///     async move { format!("{} {:?}", title, slog_scope::logger()) }.with_logger_boxed_local(&logger);
+    ///
+    /// assert_eq!(futures_executor::block_on(handler), "main Logger(window)");
+    /// ```
+    fn with_logger_boxed_local<'a, L>(
+        self,
+        logger: L,
+    ) -> Pin<Box<dyn Future<Output = Self::Output> + 'a>>
+    where
+        Self: 'a,
+        L: Borrow<Logger>,
+    {
+        Box::pin(SlogScope::new(logger.borrow().clone(), self))
+    }
+
+    /// Wrap `self` in the scope of a shared logger
+    ///
+    /// Only the `Arc` is cloned, and the wrapper is `'static`. See
+    /// [`SharedLogger`].
+    fn with_shared_logger(self, logger: &SharedLogger) -> SlogScope<SharedLogger, Self> {
+        SlogScope::new(logger.clone(), self)
+    }
+
+    /// Wrap `self` in a slog scope if `logger` is `Some`
+    ///
+    /// Otherwise, `self` is polled as is, without entering any scope. See
+    /// [`OptionalScope`].
+    fn with_optional_logger<L>(self, logger: Option<L>) -> OptionalScope<L, Self>
+    where
+        L: Borrow<Logger>,
+    {
+        OptionalScope::new(logger, self)
+    }
+
+    /// Wrap `self` in the scope active at the time of this call
+    ///
+    /// The logger is captured right away, so a task spawned from a scoped
+    /// future keeps its context wherever it ends up running. This is unlike
+    /// [`with_kv`](Self::with_kv), which derives its logger from the scope
+    /// it's first polled in.
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// async fn sub_task() -> String {
+    ///     format!("{:?} {:?}", std::thread::current().id(), slog_scope::logger())
+    /// }
+    ///
+    /// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+    /// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    ///
+    /// let handle = slog_scope::scope(&request, || runtime.spawn(sub_task().with_current_logger()));
+    /// let out = runtime.block_on(handle).unwrap();
+    ///
+    /// let here = format!("{:?}", std::thread::current().id());
+    /// assert!(!out.starts_with(&here));
+    /// assert!(out.ends_with("Logger(request_id)"));
+    /// ```
+    fn with_current_logger(self) -> SlogScope<Logger, Self> {
+        SlogScope::new(slog_scope::logger(), self)
+    }
+
+    /// Wrap `self` in a child of the scope it's first polled in, holding
+    /// `values`
+    ///
+    /// Unlike building the child logger up front, this picks up whatever
+    /// scope the future ends up running under. See [`WithKv`].
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::future::Future;
+    /// use slog_scope_futures::FutureExt;
+    ///
+    /// fn assert_spawnable<F: Future + Send + 'static>(_: &F) {}
+    ///
+    /// assert_spawnable(&async {}.with_kv(o!("job" => "cleanup")));
+    /// ```
+    fn with_kv<T>(self, values: OwnedKV<T>) -> WithKv<T, Self>
+    where
+        T: SendSyncRefUnwindSafeKV + 'static,
+    {
+        WithKv::new(values, self)
+    }
+
+    /// Wrap `self` in a slog scope adding `values` to whichever logger is
+    /// active when it's polled
+    ///
+    /// The outer scope's KVs are kept, rather than replaced. See
+    /// [`InheritKv`].
+    fn inherit_kv<T>(self, values: OwnedKV<T>) -> InheritKv<T, Self>
+    where
+        T: SendSyncRefUnwindSafeKV + 'static,
+    {
+        InheritKv::new(values, self)
+    }
+
+    /// Wrap `self` in a slog scope, building the logger with `make_logger`
+    /// on first poll
+    ///
+    /// This saves building loggers for futures that may never be polled. See
+    /// [`WithLoggerFn`].
+    fn with_logger_fn<M>(self, make_logger: M) -> WithLoggerFn<M, Self>
+    where
+        M: FnOnce() -> Logger,
+    {
+        WithLoggerFn::new(make_logger, self)
+    }
+
+    /// Log the error `self` resolves to, if any, at `level`
+    ///
+    /// The error's `Display` is the message, and its `Debug` goes under an
+    /// `err` KV. The output is passed through as is. See [`LogErr`].
+    fn log_err<T, E>(self, level: Level) -> LogErr<Self>
+    where
+        Self: Future<Output = Result<T, E>>,
+        E: fmt::Display + fmt::Debug,
+    {
+        LogErr::new(self, level)
+    }
+
+    /// Log the outcome of `self` as operation `op`, at `success` or
+    /// `failure` level
+    ///
+    /// This works for futures resolving to a `Result`, or to `()`. The
+    /// output is passed through as is. See [`LogOutcome`].
+    fn log_outcome(self, op: &'static str, success: Level, failure: Level) -> LogOutcome<Self, ()>
+    where
+        Self::Output: Outcome,
+    {
+        LogOutcome::new(self, op, success, failure)
+    }
+
+    /// Log the completion of `self` as operation `op` succeeding, at
+    /// `level`
+    ///
+    /// This works for futures resolving to anything, which is always a
+    /// success. The output is passed through as is. See [`LogSuccess`].
+    fn log_success(self, op: &'static str, level: Level) -> LogSuccess<Self, ()> {
+        LogSuccess::new(self, op, level)
+    }
+
+    /// Race `self` against a `tokio` timeout, logging expiry at `level`
+    /// with `msg`
+    ///
+    /// See [`TimeoutScoped`](crate::tokio::TimeoutScoped).
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    fn timeout_scoped(
+        self,
+        duration: std::time::Duration,
+        level: Level,
+        msg: &'static str,
+    ) -> crate::tokio::TimeoutScoped<Self> {
+        crate::tokio::TimeoutScoped::new(self, None, duration, level, msg)
+    }
+}
+
+impl<F> FutureExt for F where F: Future {}
@@ -0,0 +1,124 @@
+//! [`ScopedServe`] runs each request served by a tarpc server under a logger
+//! of its own, and [`ScopedStub`] does the same for the calls of a client.
+//! Both loggers carry the `rpc_method` and `trace_id` of the request:
+//!
+//! ```
+//! # use slog::o;
+//! use futures_util::StreamExt;
+//! use slog_scope_futures::tarpc::{ScopedServe, ScopedStub};
+//! use tarpc::{client, context, server::{BaseChannel, Channel}};
+//!
+//! #[tarpc::service]
+//! trait World {
+//!     async fn hello(name: String) -> String;
+//! }
+//!
+//! #[derive(Clone)]
+//! struct HelloServer;
+//!
+//! impl World for HelloServer {
+//!     async fn hello(self, _: context::Context, name: String) -> String {
+//!         format!("{} {:?}", name, slog_scope::logger())
+//!     }
+//! }
+//!
+//! let server = slog::Logger::root(slog::Discard, o!("side" => "server"));
+//! let client = slog::Logger::root(slog::Discard, o!("side" => "client"));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+//! runtime.block_on(async {
+//!     let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
+//!
+//!     let requests = BaseChannel::with_defaults(server_transport)
+//!         .execute(ScopedServe::new(server, HelloServer.serve()));
+//!     tokio::spawn(requests.for_each(|response| async { tokio::spawn(response); }));
+//!
+//!     let channel = client::new(client::Config::default(), client_transport).spawn();
+//!     let hello = WorldClient::from(ScopedStub::new(client, channel));
+//!
+//!     let scope = hello.hello(context::current(), "world".into()).await.unwrap();
+//!     assert_eq!(scope, "world Logger(trace_id, rpc_method, side)");
+//! });
+//! ```
+//!
+//! [`ScopedServe`]: crate::tarpc::ScopedServe
+//! [`ScopedStub`]: crate::tarpc::ScopedStub
+
+use slog::{o, Logger};
+use tarpc::{
+    client::{stub::Stub, RpcError},
+    context,
+    server::Serve,
+    RequestName, ServerError,
+};
+
+use super::SlogScope;
+
+/// Derive the logger of a request from `logger`.
+fn request_logger(logger: &Logger, ctx: &context::Context, req: &impl RequestName) -> Logger {
+    logger.new(o!(
+        "rpc_method" => req.name().to_owned(),
+        "trace_id" => ctx.trace_id().to_string(),
+    ))
+}
+
+/// A `Serve` running each request under a child logger of its own.
+///
+/// The child logger carries `rpc_method` and `trace_id` KVs.
+#[derive(Clone, Debug)]
+pub struct ScopedServe<S> {
+    logger: Logger,
+    inner: S,
+}
+
+impl<S> ScopedServe<S> {
+    /// Serve requests with `inner`, deriving their loggers from `logger`.
+    pub fn new(logger: Logger, inner: S) -> Self {
+        ScopedServe { logger, inner }
+    }
+}
+
+impl<S> Serve for ScopedServe<S>
+where
+    S: Serve,
+{
+    type Req = S::Req;
+    type Resp = S::Resp;
+
+    async fn serve(self, ctx: context::Context, req: S::Req) -> Result<S::Resp, ServerError> {
+        let logger = request_logger(&self.logger, &ctx, &req);
+
+        SlogScope::new(logger, self.inner.serve(ctx, req)).await
+    }
+}
+
+/// A client `Stub` running each call under a child logger of its own.
+///
+/// The child logger carries `rpc_method` and `trace_id` KVs, and is active
+/// for as long as the call is in flight.
+#[derive(Clone, Debug)]
+pub struct ScopedStub<S> {
+    logger: Logger,
+    inner: S,
+}
+
+impl<S> ScopedStub<S> {
+    /// Make calls through `inner`, deriving their loggers from `logger`.
+    pub fn new(logger: Logger, inner: S) -> Self {
+        ScopedStub { logger, inner }
+    }
+}
+
+impl<S> Stub for ScopedStub<S>
+where
+    S: Stub,
+{
+    type Req = S::Req;
+    type Resp = S::Resp;
+
+    async fn call(&self, ctx: context::Context, req: S::Req) -> Result<S::Resp, RpcError> {
+        let logger = request_logger(&self.logger, &ctx, &req);
+
+        SlogScope::new(logger, self.inner.call(ctx, req)).await
+    }
+}
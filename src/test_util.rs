@@ -0,0 +1,487 @@
+//! In-memory capture of log records, for asserting that futures log the
+//! right things under the right scope:
+//!
+//! ```
+//! # use slog::{info, o};
+//! use slog_scope_futures::{test_util::capture, FutureExt};
+//!
+//! async fn op() {
+//!     info!(slog_scope::logger(), "fetched"; "rows" => 3);
+//! }
+//!
+//! let (logger, records) = capture();
+//! let logger = logger.new(o!("request_id" => 42));
+//!
+//! futures_executor::block_on(op().with_logger(logger));
+//!
+//! assert!(records.contains_msg("fetched"));
+//! assert!(records.contains_kv("request_id", "42"));
+//! assert_eq!(records.records()[0].kv("rows"), Some("3"));
+//! ```
+//!
+//! The drain can be shared by tasks on multiple threads:
+//!
+//! ```
+//! # use slog::{info, o};
+//! use slog_scope_futures::{test_util::capture, FutureExt};
+//!
+//! let (logger, records) = capture();
+//!
+//! let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+//! runtime.block_on(async {
+//!     let tasks: Vec<_> = (0..8)
+//!         .map(|i| {
+//!             let task = async { info!(slog_scope::logger(), "handled") };
+//!             tokio::spawn(task.with_logger(logger.new(o!("conn" => i))))
+//!         })
+//!         .collect();
+//!     for task in tasks {
+//!         task.await.unwrap();
+//!     }
+//! });
+//!
+//! assert_eq!(records.len(), 8);
+//! assert!((0..8).all(|i| records.contains_kv("conn", &i.to_string())));
+//! ```
+//!
+//! [`ScopeProbe`](crate::test_util::ScopeProbe) and
+//! [`ManualExecutor`](crate::test_util::ManualExecutor) check the scope
+//! itself, poll by poll:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::{
+//!     test_util::{active_kvs, ManualExecutor, ScopeProbe},
+//!     FutureExt,
+//! };
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("request_id" => 42));
+//! let probe = ScopeProbe::new();
+//!
+//! let mut executor = ManualExecutor::new(probe.future(2).with_logger(&logger));
+//! while executor.poll().is_pending() {
+//!     // Not active between polls.
+//!     assert!(active_kvs().is_empty());
+//! }
+//!
+//! assert_eq!(probe.polls(), 3);
+//! assert!(probe.always_in(&logger));
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+use futures_task::noop_waker;
+use slog::{
+    b, o, record_static, Drain, Key, Level, Logger, Never, OwnedKVList, Record, Serializer, KV,
+};
+use slog_scope::GlobalLoggerGuard;
+
+/// Build a logger writing to a new [`CapturingDrain`].
+pub fn capture() -> (Logger, CapturingDrain) {
+    let drain = CapturingDrain::new();
+
+    (drain.logger(), drain)
+}
+
+/// A `Drain` keeping every record it's given in memory.
+///
+/// Clones share the same records, so one can be kept for assertions while
+/// another is moved into a logger.
+#[derive(Clone, Debug, Default)]
+pub struct CapturingDrain {
+    records: Arc<Mutex<Vec<CapturedRecord>>>,
+}
+
+impl CapturingDrain {
+    /// Create an empty drain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a root logger writing to this drain.
+    pub fn logger(&self) -> Logger {
+        Logger::root(self.clone(), o!())
+    }
+
+    /// Install a logger writing to this drain as the slog-scope global one,
+    /// until the returned guard is dropped.
+    ///
+    /// This catches records logged outside of any scope, e.g. by futures
+    /// that should have been wrapped:
+    ///
+    /// ```
+    /// # use slog::info;
+    /// use slog_scope_futures::test_util::CapturingDrain;
+    ///
+    /// let records = CapturingDrain::new();
+    /// let guard = records.set_global();
+    ///
+    /// futures_executor::block_on(async { info!(slog_scope::logger(), "unscoped") });
+    ///
+    /// drop(guard);
+    /// assert!(records.contains_msg("unscoped"));
+    /// ```
+    ///
+    /// The global logger is shared by the whole process, so tests running in
+    /// parallel will see each other's records.
+    pub fn set_global(&self) -> GlobalLoggerGuard {
+        slog_scope::set_global_logger(self.logger())
+    }
+
+    /// Get a copy of the records captured so far.
+    pub fn records(&self) -> Vec<CapturedRecord> {
+        self.lock().clone()
+    }
+
+    /// Get the number of records captured so far.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check whether no records were captured yet.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Check whether any record has the message `msg`.
+    pub fn contains_msg(&self, msg: &str) -> bool {
+        self.lock().iter().any(|record| record.msg == msg)
+    }
+
+    /// Check whether any record has `key` set to `value`, either on the
+    /// record itself or on its logger.
+    pub fn contains_kv(&self, key: &str, value: &str) -> bool {
+        self.lock()
+            .iter()
+            .any(|record| record.kv(key) == Some(value))
+    }
+
+    /// Forget the records captured so far.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<CapturedRecord>> {
+        // A panicking assertion shouldn't hide the records from other tests.
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drain for CapturingDrain {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<(), Never> {
+        let mut kvs = BTreeMap::new();
+        // KVs are serialized newest first, and the newest value for a key is
+        // the one that's kept.
+        let _ = record.kv().serialize(record, &mut Collect(&mut kvs));
+        let _ = values.serialize(record, &mut Collect(&mut kvs));
+
+        self.lock().push(CapturedRecord {
+            level: record.level(),
+            msg: record.msg().to_string(),
+            kvs,
+        });
+
+        Ok(())
+    }
+}
+
+/// A record captured by a [`CapturingDrain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedRecord {
+    level: Level,
+    msg: String,
+    kvs: BTreeMap<String, String>,
+}
+
+impl CapturedRecord {
+    /// Get the record's level.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Get the record's message.
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    /// Get the value of `key`, formatted with `Display`, from the record or
+    /// its logger.
+    pub fn kv(&self, key: &str) -> Option<&str> {
+        self.kvs.get(key).map(String::as_str)
+    }
+
+    /// Get all KVs of the record and its logger.
+    pub fn kvs(&self) -> &BTreeMap<String, String> {
+        &self.kvs
+    }
+}
+
+struct Collect<'a>(&'a mut BTreeMap<String, String>);
+
+impl Serializer for Collect<'_> {
+    fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments<'_>) -> slog::Result {
+        self.0
+            .entry(key.to_string())
+            .or_insert_with(|| val.to_string());
+        Ok(())
+    }
+}
+
+/// Get the KVs of `logger`, formatted with `Display`.
+///
+/// When a key is set more than once, the newest value is kept.
+pub fn logger_kvs(logger: &Logger) -> BTreeMap<String, String> {
+    // Serializing needs a record, though none of its fields are used here.
+    fn collect(values: &OwnedKVList, msg: fmt::Arguments<'_>) -> BTreeMap<String, String> {
+        let mut kvs = BTreeMap::new();
+        let location = record_static!(Level::Info, "");
+        let record = Record::new(&location, &msg, b!());
+        let _ = values.serialize(&record, &mut Collect(&mut kvs));
+
+        kvs
+    }
+
+    collect(logger.list(), format_args!(""))
+}
+
+/// Get the KVs of the logger that's currently active on this thread.
+///
+/// Outside of any scope, these are the slog-scope global logger's, which has
+/// none by default.
+pub fn active_kvs() -> BTreeMap<String, String> {
+    slog_scope::with_logger(logger_kvs)
+}
+
+/// A recorder of the scope futures and streams are polled in.
+///
+/// Each poll of [`future`](Self::future) or [`stream`](Self::stream)
+/// records the [`active_kvs`]. Clones share the same recordings.
+#[derive(Clone, Debug, Default)]
+pub struct ScopeProbe {
+    polls: Arc<Mutex<Vec<BTreeMap<String, String>>>>,
+}
+
+impl ScopeProbe {
+    /// Create a probe that hasn't recorded anything yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a future that's pending `pending` times, waking itself each time,
+    /// before resolving.
+    pub fn future(&self, pending: usize) -> ProbeFuture {
+        ProbeFuture {
+            probe: self.clone(),
+            pending,
+        }
+    }
+
+    /// Get a stream yielding `0..items`, with a pending poll before each
+    /// item.
+    pub fn stream(&self, items: usize) -> ProbeStream {
+        ProbeStream {
+            probe: self.clone(),
+            next: 0,
+            items,
+            ready: false,
+        }
+    }
+
+    /// Get the number of polls recorded so far.
+    pub fn polls(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Get the KVs that were active during each poll.
+    pub fn scopes(&self) -> Vec<BTreeMap<String, String>> {
+        self.lock().clone()
+    }
+
+    /// Check whether there were polls, all with `logger` active.
+    ///
+    /// Loggers are compared by their KVs.
+    pub fn always_in(&self, logger: &Logger) -> bool {
+        let expected = logger_kvs(logger);
+        let polls = self.lock();
+
+        !polls.is_empty() && polls.iter().all(|kvs| *kvs == expected)
+    }
+
+    fn record(&self) {
+        let kvs = active_kvs();
+        self.lock().push(kvs);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<BTreeMap<String, String>>> {
+        self.polls.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A `Future` recording the scope of each of its polls.
+///
+/// Created by [`ScopeProbe::future`].
+#[derive(Debug)]
+pub struct ProbeFuture {
+    probe: ScopeProbe,
+    pending: usize,
+}
+
+impl Future for ProbeFuture {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.probe.record();
+
+        if self.pending == 0 {
+            return Poll::Ready(());
+        }
+
+        self.pending -= 1;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// A `Stream` recording the scope of each of its polls.
+///
+/// Created by [`ScopeProbe::stream`].
+#[derive(Debug)]
+pub struct ProbeStream {
+    probe: ScopeProbe,
+    next: usize,
+    items: usize,
+    ready: bool,
+}
+
+impl Stream for ProbeStream {
+    type Item = usize;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<usize>> {
+        self.probe.record();
+
+        if self.next == self.items {
+            return Poll::Ready(None);
+        }
+
+        if !self.ready {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.ready = false;
+
+        self.next += 1;
+        Poll::Ready(Some(self.next - 1))
+    }
+}
+
+/// A driver polling a future or stream one step at a time, on the current
+/// thread, with a no-op waker.
+///
+/// ```
+/// # use slog::o;
+/// use std::task::Poll;
+/// use slog_scope_futures::{
+///     futures03::StreamExt,
+///     test_util::{active_kvs, ManualExecutor, ScopeProbe},
+/// };
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+/// let probe = ScopeProbe::new();
+///
+/// let mut executor = ManualExecutor::new(probe.stream(2).with_logger(&logger));
+/// let mut items = Vec::new();
+/// loop {
+///     match executor.poll_next() {
+///         Poll::Ready(Some(item)) => items.push(item),
+///         Poll::Ready(None) => break,
+///         Poll::Pending => {}
+///     }
+///     assert!(active_kvs().is_empty());
+/// }
+///
+/// assert_eq!(items, [0, 1]);
+/// assert_eq!(probe.polls(), 5);
+/// assert!(probe.always_in(&logger));
+/// ```
+pub struct ManualExecutor<F> {
+    inner: Pin<Box<F>>,
+    waker: Waker,
+    polls: usize,
+}
+
+impl<F> ManualExecutor<F> {
+    /// Wrap a future or stream to be polled manually.
+    pub fn new(inner: F) -> Self {
+        ManualExecutor {
+            inner: Box::pin(inner),
+            waker: noop_waker(),
+            polls: 0,
+        }
+    }
+
+    /// Get the number of polls made so far.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Get the wrapped future or stream.
+    pub fn get_pin_mut(&mut self) -> Pin<&mut F> {
+        self.inner.as_mut()
+    }
+
+    /// Poll the wrapped future once.
+    pub fn poll(&mut self) -> Poll<F::Output>
+    where
+        F: Future,
+    {
+        self.polls += 1;
+        let mut cx = Context::from_waker(&self.waker);
+        self.inner.as_mut().poll(&mut cx)
+    }
+
+    /// Poll the wrapped future until it's ready.
+    ///
+    /// This spins rather than waiting to be woken, so it's only suitable for
+    /// futures that don't wait on anything outside of the current thread.
+    pub fn run(&mut self) -> F::Output
+    where
+        F: Future,
+    {
+        loop {
+            if let Poll::Ready(out) = self.poll() {
+                return out;
+            }
+        }
+    }
+
+    /// Poll the wrapped stream for its next item once.
+    pub fn poll_next(&mut self) -> Poll<Option<F::Item>>
+    where
+        F: Stream,
+    {
+        self.polls += 1;
+        let mut cx = Context::from_waker(&self.waker);
+        self.inner.as_mut().poll_next(&mut cx)
+    }
+}
+
+impl<F> fmt::Debug for ManualExecutor<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManualExecutor")
+            .field("polls", &self.polls)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,50 @@
+//! OS threads spawned through
+//! [`spawn_with_scope`](crate::thread::spawn_with_scope) run under the logger
+//! that was current when they were spawned, e.g. for CPU-bound work started
+//! from a request handler:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::thread::{spawn_with_logger, spawn_with_scope};
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//!
+//! let worker = slog_scope::scope(&request, || {
+//!     spawn_with_scope(|| format!("{:?}", slog_scope::logger()))
+//! });
+//! assert_eq!(worker.join().unwrap(), "Logger(request_id)");
+//!
+//! let job = request.new(o!("job" => 1));
+//! let worker = spawn_with_logger(job, || format!("{:?}", slog_scope::logger()));
+//! assert_eq!(worker.join().unwrap(), "Logger(job, request_id)");
+//! ```
+//!
+//! These are unrelated to `std::thread::scope`, whose threads borrow from the
+//! spawning stack frame; wrap their closures with
+//! [`FnOnceExt::with_logger`](crate::closure::FnOnceExt::with_logger) instead.
+
+use std::thread::{self, JoinHandle};
+
+use slog::Logger;
+
+use super::closure::ScopedFnOnce;
+
+/// Spawn an OS thread running `f` in the current scope.
+///
+/// The current logger is captured when this is called.
+pub fn spawn_with_scope<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    spawn_with_logger(slog_scope::logger(), f)
+}
+
+/// Spawn an OS thread running `f` in `logger`'s scope.
+pub fn spawn_with_logger<F, R>(logger: Logger, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    thread::spawn(ScopedFnOnce::new(logger, f).into_fn())
+}
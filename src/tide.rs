@@ -0,0 +1,125 @@
+//! [`ScopeMiddleware`] runs the rest of a tide app under a logger derived
+//! for each request. Handlers can log through `slog_scope` as usual, or grab
+//! the request's logger with [`RequestExt::scoped_logger`].
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::tide::{child_logger, RequestExt, ScopeMiddleware};
+//! use tide::http::{Method, Request, Response, Url};
+//!
+//! let root = slog::Logger::root(slog::Discard, o!());
+//!
+//! let mut app = tide::new();
+//! app.with(ScopeMiddleware::new(move |req: &tide::Request<()>| child_logger(&root, req)));
+//! app.at("/hello").get(|req: tide::Request<()>| async move {
+//!     assert_eq!(format!("{:?}", req.scoped_logger()), format!("{:?}", slog_scope::logger()));
+//!     Ok(format!("{:?}", slog_scope::logger()))
+//! });
+//!
+//! let req = Request::new(Method::Get, Url::parse("http://example.com/hello").unwrap());
+//! let mut res: Response = futures_executor::block_on(app.respond(req)).unwrap();
+//! let body = futures_executor::block_on(res.body_string()).unwrap();
+//! assert_eq!(body, "Logger(request_id, path, method)");
+//! ```
+//!
+//! [`ScopeMiddleware`]: crate::tide::ScopeMiddleware
+//! [`RequestExt::scoped_logger`]: crate::tide::RequestExt::scoped_logger
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use slog::{o, Logger};
+use tide::{Middleware, Next, Request};
+
+use super::SlogScope;
+
+/// Header a request id is taken from by [`child_logger`], if present.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// A `Middleware` running each request under a logger of its own.
+///
+/// The logger is made by `make_logger`, e.g. with [`child_logger`], and
+/// inserted into the request extensions for [`RequestExt::scoped_logger`].
+#[derive(Clone, Debug)]
+pub struct ScopeMiddleware<F> {
+    make_logger: F,
+}
+
+impl<F> ScopeMiddleware<F> {
+    /// Create a middleware deriving request loggers with `make_logger`.
+    pub fn new<State>(make_logger: F) -> Self
+    where
+        F: Fn(&Request<State>) -> Logger,
+    {
+        ScopeMiddleware { make_logger }
+    }
+}
+
+impl<State, F> Middleware<State> for ScopeMiddleware<F>
+where
+    State: Clone + Send + Sync + 'static,
+    F: Fn(&Request<State>) -> Logger + Send + Sync + 'static,
+{
+    fn handle<'a, 'b, 't>(
+        &'a self,
+        mut req: Request<State>,
+        next: Next<'b, State>,
+    ) -> Pin<Box<dyn Future<Output = tide::Result> + Send + 't>>
+    where
+        'a: 't,
+        'b: 't,
+        Self: 't,
+    {
+        let logger = (self.make_logger)(&req);
+        req.set_ext(logger.clone());
+
+        Box::pin(SlogScope::new(
+            logger,
+            async move { Ok(next.run(req).await) },
+        ))
+    }
+}
+
+/// Derive a request logger from `logger`.
+///
+/// The child logger carries `method`, `path` and `request_id` KVs, the
+/// latter taken from the `x-request-id` header or generated if the header is
+/// missing.
+pub fn child_logger<State>(logger: &Logger, req: &Request<State>) -> Logger {
+    let request_id = match req.header(REQUEST_ID_HEADER) {
+        Some(id) => id.last().as_str().to_owned(),
+        None => next_request_id().to_string(),
+    };
+
+    logger.new(o!(
+        "method" => req.method().to_string(),
+        "path" => req.url().path().to_owned(),
+        "request_id" => request_id,
+    ))
+}
+
+fn next_request_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Extension trait for getting the logger of a tide request.
+pub trait RequestExt {
+    /// Get the logger set up by [`ScopeMiddleware`].
+    ///
+    /// Falls back to the current scope's logger if the middleware isn't in
+    /// use.
+    fn scoped_logger(&self) -> Logger;
+}
+
+impl<State> RequestExt for Request<State> {
+    fn scoped_logger(&self) -> Logger {
+        self.ext::<Logger>()
+            .cloned()
+            .unwrap_or_else(slog_scope::logger)
+    }
+}
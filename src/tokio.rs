@@ -0,0 +1,526 @@
+//! Wrapping a whole connection lets every codec-driven read and write carry
+//! the connection's KVs:
+//!
+//! ```
+//! # use slog::o;
+//! # use std::{io, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+//! use slog_scope_futures::SlogScope;
+//! use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream, ReadBuf};
+//!
+//! /// A connection recording the active scope whenever it's read from or
+//! /// written to.
+//! struct Conn(DuplexStream, Arc<Mutex<Vec<String>>>);
+//!
+//! impl Conn {
+//!     fn record(&self) {
+//!         self.1.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+//!     }
+//! }
+//!
+//! impl tokio::io::AsyncRead for Conn {
+//!     fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+//!         self.record();
+//!         Pin::new(&mut self.0).poll_read(cx, buf)
+//!     }
+//! }
+//!
+//! impl tokio::io::AsyncWrite for Conn {
+//!     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+//!         self.record();
+//!         Pin::new(&mut self.0).poll_write(cx, buf)
+//!     }
+//!
+//!     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+//!         self.record();
+//!         Pin::new(&mut self.0).poll_flush(cx)
+//!     }
+//!
+//!     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+//!         self.record();
+//!         Pin::new(&mut self.0).poll_shutdown(cx)
+//!     }
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("conn_id" => 1));
+//! let records = Arc::new(Mutex::new(Vec::new()));
+//! let (client, mut server) = tokio::io::duplex(64);
+//! let mut conn = SlogScope::new(logger, Conn(client, records.clone()));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! runtime.block_on(async {
+//!     conn.write_all(b"ping").await.unwrap();
+//!     conn.flush().await.unwrap();
+//!
+//!     let mut buf = [0; 4];
+//!     server.read_exact(&mut buf).await.unwrap();
+//!     server.write_all(&buf).await.unwrap();
+//!     conn.read_exact(&mut buf).await.unwrap();
+//!     assert_eq!(&buf, b"ping");
+//!
+//!     conn.shutdown().await.unwrap();
+//! });
+//!
+//! let records = records.lock().unwrap();
+//! assert!(records.len() >= 4);
+//! assert!(records.iter().all(|r| r == "Logger(conn_id)"));
+//! ```
+
+use std::{
+    borrow::Borrow,
+    future::{self, Future, Pending},
+    io::{self, IoSlice},
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use pin_project_lite::pin_project;
+use slog::{Level, Logger};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    task::JoinHandle,
+    time::{error::Elapsed, Instant, Timeout},
+};
+
+use super::{closure::ScopedFnOnce, instrument::log_at, SlogScope};
+
+impl<L, R> AsyncRead for SlogScope<L, R>
+where
+    R: AsyncRead,
+    L: Borrow<Logger>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.scoped(|inner| inner.poll_read(cx, buf))
+    }
+}
+
+impl<L, W> AsyncWrite for SlogScope<L, W>
+where
+    W: AsyncWrite,
+    L: Borrow<Logger>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_write(cx, buf))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.scoped(|inner| inner.poll_write_vectored(cx, bufs))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.get_ref().is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.scoped(|inner| inner.poll_flush(cx))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.scoped(|inner| inner.poll_shutdown(cx))
+    }
+}
+
+/// Spawn a task running in the current scope.
+///
+/// The current logger is captured when this is called, so the task keeps its
+/// KVs on whichever worker thread it runs:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tokio::spawn_scoped;
+///
+/// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// let out = runtime.block_on(async {
+///     let task = slog_scope::scope(&request, || {
+///         spawn_scoped(async { format!("{:?}", slog_scope::logger()) })
+///     });
+///     task.await.unwrap()
+/// });
+/// assert_eq!(out, "Logger(request_id)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::spawn`, this panics when called outside of a runtime.
+pub fn spawn_scoped<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    spawn_scoped_with(slog_scope::logger(), future)
+}
+
+/// Spawn a task running in `logger`'s scope.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tokio::spawn_scoped_with;
+///
+/// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+/// let job = slog::Logger::root(slog::Discard, o!("job" => "cleanup"));
+///
+/// let task = runtime.spawn(async move {
+///     spawn_scoped_with(job, async { format!("{:?}", slog_scope::logger()) }).await
+/// });
+/// assert_eq!(runtime.block_on(task).unwrap().unwrap(), "Logger(job)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::spawn`, this panics when called outside of a runtime.
+pub fn spawn_scoped_with<F>(logger: Logger, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(SlogScope::new(logger, future))
+}
+
+/// Spawn a `!Send` task on the current `LocalSet`, running in the current
+/// scope.
+///
+/// ```
+/// # use slog::o;
+/// use std::rc::Rc;
+/// use slog_scope_futures::tokio::spawn_local_scoped;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let local = tokio::task::LocalSet::new();
+/// let window = slog::Logger::root(slog::Discard, o!("window" => 1));
+///
+/// let out = local.block_on(&runtime, async {
+///     let title = Rc::new("main");
+///     let task = slog_scope::scope(&window, || {
+///         spawn_local_scoped(async move { format!("{} {:?}", title, slog_scope::logger()) })
+///     });
+///     task.await.unwrap()
+/// });
+/// assert_eq!(out, "main Logger(window)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::task::spawn_local`, this panics when called outside of a
+/// `LocalSet`.
+pub fn spawn_local_scoped<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    spawn_local_scoped_with(slog_scope::logger(), future)
+}
+
+/// Spawn a `!Send` task on the current `LocalSet`, running in `logger`'s
+/// scope.
+///
+/// Neither the future nor the logger need to be `Send`:
+///
+/// ```
+/// # use slog::o;
+/// use std::rc::Rc;
+/// use slog_scope_futures::tokio::spawn_local_scoped_with;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let local = tokio::task::LocalSet::new();
+/// let window = Rc::new(slog::Logger::root(slog::Discard, o!("window" => 1)));
+///
+/// let out = local.block_on(&runtime, async {
+///     spawn_local_scoped_with(window, async { format!("{:?}", slog_scope::logger()) }).await
+/// });
+/// assert_eq!(out.unwrap(), "Logger(window)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::task::spawn_local`, this panics when called outside of a
+/// `LocalSet`.
+pub fn spawn_local_scoped_with<L, F>(logger: L, future: F) -> JoinHandle<F::Output>
+where
+    L: Borrow<Logger> + 'static,
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    tokio::task::spawn_local(SlogScope::new(logger, future))
+}
+
+/// Run a blocking closure on tokio's blocking pool in the current scope.
+///
+/// As with [`spawn_scoped`], the current logger is captured when this is
+/// called:
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tokio::spawn_blocking_scoped;
+///
+/// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// let out = runtime.block_on(async {
+///     let task = slog_scope::scope(&request, || {
+///         spawn_blocking_scoped(|| format!("{:?}", slog_scope::logger()))
+///     });
+///     task.await.unwrap()
+/// });
+/// assert_eq!(out, "Logger(request_id)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::task::spawn_blocking`, this panics when called outside of a
+/// runtime.
+pub fn spawn_blocking_scoped<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    spawn_blocking_scoped_with(slog_scope::logger(), f)
+}
+
+/// Run a blocking closure on tokio's blocking pool in `logger`'s scope.
+///
+/// # Panics
+///
+/// Like `tokio::task::spawn_blocking`, this panics when called outside of a
+/// runtime.
+pub fn spawn_blocking_scoped_with<F, R>(logger: Logger, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(ScopedFnOnce::new(logger, f).into_fn())
+}
+
+/// Run a blocking section through `tokio::task::block_in_place` in the
+/// current scope.
+///
+/// The closure runs on the current thread, so it would see the current scope
+/// anyway. This makes it explicit, and that the scope is kept for anything
+/// the section hands the captured logger to.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::{tokio::block_in_place_scoped, FutureExt};
+///
+/// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+/// let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+///
+/// let handler = async { block_in_place_scoped(|| format!("{:?}", slog_scope::logger())) };
+/// let out = runtime.block_on(runtime.spawn(handler.with_logger(request))).unwrap();
+/// assert_eq!(out, "Logger(request_id)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::task::block_in_place`, this panics when called from a
+/// current-thread runtime.
+pub fn block_in_place_scoped<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    block_in_place_scoped_with(slog_scope::logger(), f)
+}
+
+/// Run a blocking section through `tokio::task::block_in_place` in
+/// `logger`'s scope.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tokio::block_in_place_scoped_with;
+///
+/// let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+/// let section = slog::Logger::root(slog::Discard, o!("section" => "checksum"));
+///
+/// let out = runtime.block_on(runtime.spawn(async move {
+///     block_in_place_scoped_with(&section, || format!("{:?}", slog_scope::logger()))
+/// }));
+/// assert_eq!(out.unwrap(), "Logger(section)");
+/// ```
+///
+/// # Panics
+///
+/// Like `tokio::task::block_in_place`, this panics when called from a
+/// current-thread runtime.
+pub fn block_in_place_scoped_with<L, F, R>(logger: L, f: F) -> R
+where
+    L: Borrow<Logger>,
+    F: FnOnce() -> R,
+{
+    tokio::task::block_in_place(|| ScopedFnOnce::new(logger, f).call())
+}
+
+pin_project! {
+    /// A `Future` racing the inner future against a timeout, logging expiry.
+    ///
+    /// Created by [`FutureExt::timeout_scoped`](crate::std_future::FutureExt::timeout_scoped),
+    /// or [`SlogScope::timeout_scoped`]. On expiry, a record is logged with
+    /// `timeout_ms` and `elapsed_ms` KVs, the latter counted from the first
+    /// poll, and the future resolves to `Err(Elapsed)`. The timer is only
+    /// started on first poll, so the wrapper can be built outside of the
+    /// runtime, and it's dropped as soon as the inner future completes.
+    ///
+    /// The record is logged in the scope of the wrapped [`SlogScope`] if
+    /// created through [`SlogScope::timeout_scoped`], or the one active at
+    /// expiry otherwise, so both orders log with the scope's KVs:
+    ///
+    /// ```
+    /// # use slog::o;
+    /// use std::time::Duration;
+    /// use slog::Level;
+    /// use slog_scope_futures::{test_util::capture, FutureExt};
+    ///
+    /// let (logger, records) = capture();
+    /// let logger = logger.new(o!("request_id" => 1));
+    /// let timeout = Duration::from_secs(5);
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread()
+    ///     .enable_time()
+    ///     .start_paused(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// runtime.block_on(async {
+    ///     let quick = tokio::time::sleep(Duration::from_secs(1));
+    ///     let quick = quick.timeout_scoped(timeout, Level::Warning, "timed out").with_logger(&logger);
+    ///     assert!(quick.await.is_ok());
+    ///
+    ///     let stuck = std::future::pending::<()>();
+    ///     let stuck = stuck.timeout_scoped(timeout, Level::Warning, "timed out").with_logger(&logger);
+    ///     assert!(stuck.await.is_err());
+    ///
+    ///     let stuck = std::future::pending::<()>();
+    ///     let stuck = stuck.with_logger(&logger).timeout_scoped(timeout, Level::Warning, "timed out");
+    ///     assert!(stuck.await.is_err());
+    /// });
+    ///
+    /// let records = records.records();
+    /// assert_eq!(records.len(), 2);
+    /// for record in &records {
+    ///     assert_eq!((record.level(), record.msg()), (Level::Warning, "timed out"));
+    ///     assert_eq!(record.kv("timeout_ms"), Some("5000"));
+    ///     assert_eq!(record.kv("elapsed_ms"), Some("5000"));
+    ///     assert_eq!(record.kv("request_id"), Some("1"));
+    /// }
+    /// ```
+    pub struct TimeoutScoped<F> {
+        #[pin]
+        inner: Option<F>,
+        // Set on first poll, along with `started`.
+        #[pin]
+        timer: Option<Timeout<Pending<()>>>,
+        logger: Option<Logger>,
+        duration: Duration,
+        level: Level,
+        msg: &'static str,
+        started: Option<Instant>,
+    }
+}
+
+impl<F> TimeoutScoped<F>
+where
+    F: Future,
+{
+    pub(crate) fn new(
+        inner: F,
+        logger: Option<Logger>,
+        duration: Duration,
+        level: Level,
+        msg: &'static str,
+    ) -> Self {
+        TimeoutScoped {
+            inner: Some(inner),
+            timer: None,
+            logger,
+            duration,
+            level,
+            msg,
+            started: None,
+        }
+    }
+}
+
+impl<F> Future for TimeoutScoped<F>
+where
+    F: Future,
+{
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        let inner = this
+            .inner
+            .as_mut()
+            .as_pin_mut()
+            .expect("TimeoutScoped polled after completion");
+
+        let started = match *this.started {
+            Some(started) => started,
+            None => {
+                let started = Instant::now();
+                let deadline = started + *this.duration;
+                this.timer
+                    .set(Some(tokio::time::timeout_at(deadline, future::pending())));
+                *this.started = Some(started);
+                started
+            }
+        };
+
+        // The inner future goes first, as with `tokio::time::timeout`.
+        let out = match inner.poll(cx) {
+            Poll::Ready(out) => Ok(out),
+            Poll::Pending => {
+                let timer = this.timer.as_mut().as_pin_mut().expect("timer is started");
+                Err(ready!(timer.poll(cx)).unwrap_err())
+            }
+        };
+        this.inner.set(None);
+        this.timer.set(None);
+
+        if out.is_err() {
+            let (level, msg) = (*this.level, *this.msg);
+            let timeout_ms = this.duration.as_millis();
+            let elapsed_ms = started.elapsed().as_millis();
+            let log = |logger: &Logger| log_at!(logger, level, "{}", msg; "timeout_ms" => timeout_ms, "elapsed_ms" => elapsed_ms);
+
+            match this.logger {
+                Some(logger) => log(logger),
+                None => slog_scope::with_logger(log),
+            }
+        }
+
+        Poll::Ready(out)
+    }
+}
+
+impl<L, F> SlogScope<L, F>
+where
+    L: Borrow<Logger>,
+    F: Future,
+{
+    /// Race `self` against a timeout, logging expiry in this scope.
+    ///
+    /// See [`TimeoutScoped`].
+    pub fn timeout_scoped(
+        self,
+        duration: Duration,
+        level: Level,
+        msg: &'static str,
+    ) -> TimeoutScoped<Self> {
+        let logger = self.logger().clone();
+
+        TimeoutScoped::new(self, Some(logger), duration, level, msg)
+    }
+}
@@ -0,0 +1,119 @@
+//! A codec wrapped in a slog scope logs under it while it decodes and
+//! encodes frames, whichever task ends up driving it:
+//!
+//! ```
+//! # use slog::o;
+//! use futures_util::StreamExt;
+//! use slog_scope_futures::tokio_util::CodecExt;
+//! use tokio_util::codec::{Decoder, FramedRead, LinesCodec};
+//!
+//! /// Lines, with the scope they were decoded in.
+//! struct Scopes(LinesCodec);
+//!
+//! impl Decoder for Scopes {
+//!     type Item = String;
+//!     type Error = <LinesCodec as Decoder>::Error;
+//!
+//!     fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<String>, Self::Error> {
+//!         let line = self.0.decode(src)?;
+//!         Ok(line.map(|line| format!("{} {:?}", line, slog_scope::logger())))
+//!     }
+//! }
+//!
+//! let conn = slog::Logger::root(slog::Discard, o!("conn" => 1));
+//! let io: &[u8] = b"hello\n";
+//! let mut frames = FramedRead::new(io, Scopes(LinesCodec::new()).with_logger(conn));
+//!
+//! let frame = futures_executor::block_on(frames.next()).unwrap().unwrap();
+//! assert_eq!(frame, "hello Logger(conn)");
+//! ```
+
+use std::borrow::Borrow;
+
+use bytes::BytesMut;
+use slog::Logger;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// A `Decoder` and `Encoder` wrapped in a slog scope.
+///
+/// `decode`, `decode_eof` and `encode` run in the scope. Items, errors and
+/// the handling of the buffers are left to the inner codec.
+#[derive(Clone, Debug)]
+pub struct ScopedCodec<L, C> {
+    logger: L,
+    codec: C,
+}
+
+impl<L, C> ScopedCodec<L, C>
+where
+    L: Borrow<Logger>,
+{
+    /// Wrap a codec in a slog scope.
+    pub fn new(logger: L, codec: C) -> Self {
+        ScopedCodec { logger, codec }
+    }
+
+    /// Get the logger.
+    pub fn logger(&self) -> &Logger {
+        self.logger.borrow()
+    }
+
+    /// Get a reference to the inner codec.
+    pub fn get_ref(&self) -> &C {
+        &self.codec
+    }
+
+    /// Get a mutable reference to the inner codec.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Unwrap the inner codec.
+    pub fn into_inner(self) -> C {
+        self.codec
+    }
+}
+
+impl<L, C> Decoder for ScopedCodec<L, C>
+where
+    L: Borrow<Logger>,
+    C: Decoder,
+{
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<C::Item>, C::Error> {
+        let codec = &mut self.codec;
+        slog_scope::scope(self.logger.borrow(), || codec.decode(src))
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<C::Item>, C::Error> {
+        let codec = &mut self.codec;
+        slog_scope::scope(self.logger.borrow(), || codec.decode_eof(buf))
+    }
+}
+
+impl<L, C, Item> Encoder<Item> for ScopedCodec<L, C>
+where
+    L: Borrow<Logger>,
+    C: Encoder<Item>,
+{
+    type Error = C::Error;
+
+    fn encode(&mut self, item: Item, dst: &mut BytesMut) -> Result<(), C::Error> {
+        let codec = &mut self.codec;
+        slog_scope::scope(self.logger.borrow(), || codec.encode(item, dst))
+    }
+}
+
+/// Extension trait for wrapping codecs in a slog scope.
+///
+/// Encoder-only codecs can be wrapped with [`ScopedCodec::new`].
+pub trait CodecExt: Decoder + Sized {
+    /// Run `decode`, `decode_eof` and `encode` in `logger`'s scope.
+    fn with_logger<L: Borrow<Logger>>(self, logger: L) -> ScopedCodec<L, Self> {
+        ScopedCodec::new(logger, self)
+    }
+}
+
+impl<C: Decoder> CodecExt for C {}
@@ -0,0 +1,131 @@
+//! Tonic servers are tower services, so [`GrpcScopeLayer`] can be added to
+//! them like any other layer. Each RPC runs under a logger derived from its
+//! gRPC path and request metadata, including the streaming of the response
+//! body for server-streaming RPCs:
+//!
+//! ```
+//! # use slog::o;
+//! # use std::convert::Infallible;
+//! use bytes::Bytes;
+//! use futures_util::stream::{self, StreamExt as _};
+//! use http_body::Frame;
+//! use http_body_util::{BodyExt, StreamBody};
+//! use slog_scope_futures::tonic::GrpcScopeLayer;
+//! use tonic::metadata::MetadataMap;
+//! use tower::{service_fn, ServiceBuilder, ServiceExt};
+//!
+//! fn rpc_logger(root: &slog::Logger, path: &str, metadata: &MetadataMap) -> slog::Logger {
+//!     let mut parts = path.trim_start_matches('/').splitn(2, '/');
+//!     let service = parts.next().unwrap_or_default().to_owned();
+//!     let method = parts.next().unwrap_or_default().to_owned();
+//!     let trace_id = metadata.get("x-trace-id").map(|id| id.to_str().unwrap().to_owned());
+//!
+//!     root.new(o!("service" => service, "method" => method, "trace_id" => trace_id))
+//! }
+//!
+//! /// A stand-in for a tonic server: replies with the handler's scope, then
+//! /// streams two more messages recording the scope they're produced in.
+//! async fn server(
+//!     _: http::Request<()>,
+//! ) -> Result<http::Response<impl http_body::Body<Data = Bytes, Error = Infallible>>, Infallible> {
+//!     let scope = || Ok::<_, Infallible>(Frame::data(Bytes::from(format!("{:?}", slog_scope::logger()))));
+//!     let first = scope();
+//!     let rest = stream::iter(0..2).map(move |_| scope());
+//!
+//!     Ok(http::Response::new(StreamBody::new(stream::iter([first]).chain(rest))))
+//! }
+//!
+//! let root = slog::Logger::root(slog::Discard, o!());
+//! let server = ServiceBuilder::new()
+//!     .layer(GrpcScopeLayer::new(move |path: &str, metadata: &MetadataMap| {
+//!         rpc_logger(&root, path, metadata)
+//!     }))
+//!     .service(service_fn(server));
+//!
+//! let req = http::Request::post("/helloworld.Greeter/SayHello")
+//!     .header("x-trace-id", "abc")
+//!     .body(())
+//!     .unwrap();
+//! let body = futures_executor::block_on(async {
+//!     let res = server.oneshot(req).await.unwrap();
+//!     BodyExt::collect(res.into_body()).await.unwrap().to_bytes()
+//! });
+//! assert_eq!(body, "Logger(trace_id, method, service)".repeat(3));
+//! ```
+//!
+//! [`GrpcScopeLayer`]: crate::tonic::GrpcScopeLayer
+
+use std::task::{Context, Poll};
+
+use slog::Logger;
+use tonic::metadata::MetadataMap;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use super::{tower::ResponseFuture, SlogScope};
+
+/// A `Layer` running each RPC under a logger of its own.
+///
+/// The logger is built by calling `make_logger` with the RPC's path (e.g.
+/// `/helloworld.Greeter/SayHello`) and the request's metadata.
+#[derive(Clone, Debug)]
+pub struct GrpcScopeLayer<M> {
+    make_logger: M,
+}
+
+impl<M> GrpcScopeLayer<M>
+where
+    M: Fn(&str, &MetadataMap) -> Logger,
+{
+    /// Create a layer deriving RPC loggers with `make_logger`.
+    pub fn new(make_logger: M) -> Self {
+        GrpcScopeLayer { make_logger }
+    }
+}
+
+impl<S, M> Layer<S> for GrpcScopeLayer<M>
+where
+    M: Clone,
+{
+    type Service = GrpcScope<S, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcScope {
+            inner,
+            make_logger: self.make_logger.clone(),
+        }
+    }
+}
+
+/// A service running each RPC under a logger of its own.
+///
+/// See [`GrpcScopeLayer`].
+#[derive(Clone, Debug)]
+pub struct GrpcScope<S, M> {
+    inner: S,
+    make_logger: M,
+}
+
+impl<S, M, B, ResBody> Service<http::Request<B>> for GrpcScope<S, M>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    M: Fn(&str, &MetadataMap) -> Logger,
+{
+    type Response = http::Response<SlogScope<Logger, ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let metadata = MetadataMap::from_headers(req.headers().clone());
+        let logger = (self.make_logger)(req.uri().path(), &metadata);
+
+        let inner = &mut self.inner;
+        let future = slog_scope::scope(&logger, || inner.call(req));
+
+        ResponseFuture::new(SlogScope::new(logger, future))
+    }
+}
@@ -0,0 +1,372 @@
+//! A scoped service runs `poll_ready` and `call` inside the scope, and
+//! returns response futures scoped to a clone of the same logger:
+//!
+//! ```
+//! # use slog::o;
+//! # use std::{future::Future, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+//! use slog_scope_futures::SlogScope;
+//! use tower::{Service, ServiceExt};
+//!
+//! /// A service recording the active scope whenever it's driven.
+//! #[derive(Clone)]
+//! struct Recorder(Arc<Mutex<Vec<String>>>);
+//!
+//! impl Recorder {
+//!     fn record(&self) {
+//!         self.0.lock().unwrap().push(format!("{:?}", slog_scope::logger()));
+//!     }
+//! }
+//!
+//! impl Future for Recorder {
+//!     type Output = Result<(), ()>;
+//!
+//!     fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+//!         self.record();
+//!         Poll::Ready(Ok(()))
+//!     }
+//! }
+//!
+//! impl Service<()> for Recorder {
+//!     type Response = ();
+//!     type Error = ();
+//!     type Future = Recorder;
+//!
+//!     fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+//!         self.record();
+//!         Poll::Ready(Ok(()))
+//!     }
+//!
+//!     fn call(&mut self, _: ()) -> Self::Future {
+//!         self.record();
+//!         self.clone()
+//!     }
+//! }
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("component" => "db"));
+//! let records = Arc::new(Mutex::new(Vec::new()));
+//! let mut service = SlogScope::new(logger, Recorder(records.clone()));
+//!
+//! futures_executor::block_on(async {
+//!     let response = service.ready().await.unwrap().call(());
+//!     response.await.unwrap();
+//! });
+//!
+//! let records = records.lock().unwrap();
+//! assert_eq!(records.len(), 3);
+//! assert!(records.iter().all(|r| r == "Logger(component)"));
+//! ```
+
+use std::{
+    borrow::Borrow,
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use slog::Logger;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use super::SlogScope;
+
+impl<L, S, Request> Service<Request> for SlogScope<L, S>
+where
+    S: Service<Request>,
+    L: Borrow<Logger>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = SlogScope<Logger, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.scoped_mut(|inner| inner.poll_ready(cx))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let future = self.scoped_mut(|inner| inner.call(req));
+
+        SlogScope::new(self.logger().clone(), future)
+    }
+}
+
+/// A `Layer` wrapping services in a slog scope with a fixed logger.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tower::SlogScopeLayer;
+/// use tower::{service_fn, Service, ServiceBuilder, ServiceExt};
+///
+/// let logger = slog::Logger::root(slog::Discard, o!("component" => "db"));
+///
+/// let mut service = ServiceBuilder::new()
+///     .layer(SlogScopeLayer::new(logger))
+///     .service(service_fn(|()| async {
+///         Ok::<_, ()>(format!("{:?}", slog_scope::logger()))
+///     }));
+///
+/// let scope = futures_executor::block_on(async {
+///     service.ready().await.unwrap().call(()).await.unwrap()
+/// });
+/// assert_eq!(scope, "Logger(component)");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SlogScopeLayer {
+    logger: Logger,
+}
+
+impl SlogScopeLayer {
+    /// Create a layer scoping services to `logger`.
+    pub fn new(logger: Logger) -> Self {
+        SlogScopeLayer { logger }
+    }
+}
+
+impl<S> Layer<S> for SlogScopeLayer {
+    type Service = SlogScope<Logger, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SlogScope::new(self.logger.clone(), inner)
+    }
+}
+
+/// A `MakeService` scoping each connection to a logger of its own.
+///
+/// `make_logger` is called with the connection target, e.g. the incoming
+/// stream, and the service made for the connection is wrapped in a
+/// `SlogScope` with the resulting logger. Its requests and their response
+/// futures all run under it, and so do the services it's made of: a
+/// [`ScopeFromExtensions`] without a default falls back to the connection
+/// logger, and per-request loggers derived from the current scope become
+/// children of it.
+///
+/// ```
+/// # use slog::o;
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use slog_scope_futures::tower::ScopedMakeService;
+/// use tower::{service_fn, Service, ServiceExt};
+///
+/// struct Conn {
+///     peer_addr: &'static str,
+/// }
+///
+/// let root = slog::Logger::root(slog::Discard, o!());
+/// let next_id = AtomicU64::new(0);
+/// let make_svc = service_fn(|_: &Conn| async {
+///     Ok::<_, ()>(service_fn(|()| async {
+///         Ok::<_, ()>(format!("{:?}", slog_scope::logger()))
+///     }))
+/// });
+/// let mut make_svc = ScopedMakeService::new(make_svc, |conn: &&Conn| {
+///     let conn_id = next_id.fetch_add(1, Ordering::Relaxed);
+///     root.new(o!("conn_id" => conn_id, "peer_addr" => conn.peer_addr))
+/// });
+///
+/// futures_executor::block_on(async {
+///     let conn = Conn { peer_addr: "10.0.0.1:4242" };
+///     let mut svc = make_svc.ready().await.unwrap().call(&conn).await.unwrap();
+///
+///     let scope = svc.ready().await.unwrap().call(()).await.unwrap();
+///     assert_eq!(scope, "Logger(peer_addr, conn_id)");
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct ScopedMakeService<M, F> {
+    inner: M,
+    make_logger: F,
+}
+
+impl<M, F> ScopedMakeService<M, F> {
+    /// Scope the services made by `inner` to the loggers made by
+    /// `make_logger`.
+    pub fn new(inner: M, make_logger: F) -> Self {
+        ScopedMakeService { inner, make_logger }
+    }
+}
+
+impl<M, F, Target> Service<Target> for ScopedMakeService<M, F>
+where
+    M: Service<Target>,
+    F: Fn(&Target) -> Logger,
+{
+    type Response = SlogScope<Logger, M::Response>;
+    type Error = M::Error;
+    type Future = MakeFuture<M::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, target: Target) -> Self::Future {
+        let logger = (self.make_logger)(&target);
+
+        let inner = &mut self.inner;
+        let future = slog_scope::scope(&logger, || inner.call(target));
+
+        MakeFuture {
+            inner: SlogScope::new(logger, future),
+        }
+    }
+}
+
+pin_project! {
+    /// The future of a [`ScopedMakeService`], scoping the service it makes.
+    pub struct MakeFuture<F> {
+        #[pin]
+        inner: SlogScope<Logger, F>,
+    }
+}
+
+impl<F, S, E> Future for MakeFuture<F>
+where
+    F: Future<Output = Result<S, E>>,
+{
+    type Output = Result<SlogScope<Logger, S>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.project().inner;
+        let res = ready!(inner.as_mut().poll(cx));
+        let logger = inner.logger().clone();
+
+        Poll::Ready(res.map(|service| SlogScope::new(logger, service)))
+    }
+}
+
+/// A `Layer` scoping each request to a `Logger` found in its extensions.
+///
+/// Requests without a `Logger` extension fall back to the layer's default
+/// logger if it has one, or to the logger of the scope active when the
+/// request is made otherwise.
+///
+/// ```
+/// # use slog::o;
+/// use slog_scope_futures::tower::ScopeFromExtensionsLayer;
+/// use tower::{service_fn, Service, ServiceBuilder, ServiceExt};
+///
+/// let default = slog::Logger::root(slog::Discard, o!("default" => true));
+/// let mut service = ServiceBuilder::new()
+///     .layer(ScopeFromExtensionsLayer::with_default(default))
+///     .service(service_fn(|_: http::Request<()>| async {
+///         Ok::<_, ()>(format!("{:?}", slog_scope::logger()))
+///     }));
+///
+/// futures_executor::block_on(async {
+///     let mut req = http::Request::new(());
+///     req.extensions_mut()
+///         .insert(slog::Logger::root(slog::Discard, o!("request_id" => 42)));
+///     let scope = service.ready().await.unwrap().call(req).await.unwrap();
+///     assert_eq!(scope, "Logger(request_id)");
+///
+///     let req = http::Request::new(());
+///     let scope = service.ready().await.unwrap().call(req).await.unwrap();
+///     assert_eq!(scope, "Logger(default)");
+/// });
+/// ```
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+#[derive(Clone, Debug, Default)]
+pub struct ScopeFromExtensionsLayer {
+    default: Option<Logger>,
+}
+
+#[cfg(feature = "http")]
+impl ScopeFromExtensionsLayer {
+    /// Create a layer falling back to the current scope's logger.
+    pub fn new() -> Self {
+        ScopeFromExtensionsLayer { default: None }
+    }
+
+    /// Create a layer falling back to `logger`.
+    pub fn with_default(logger: Logger) -> Self {
+        ScopeFromExtensionsLayer {
+            default: Some(logger),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl<S> Layer<S> for ScopeFromExtensionsLayer {
+    type Service = ScopeFromExtensions<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ScopeFromExtensions {
+            inner,
+            default: self.default.clone(),
+        }
+    }
+}
+
+/// A service scoping each request to a `Logger` found in its extensions.
+///
+/// See [`ScopeFromExtensionsLayer`].
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+#[derive(Clone, Debug)]
+pub struct ScopeFromExtensions<S> {
+    inner: S,
+    default: Option<Logger>,
+}
+
+#[cfg(feature = "http")]
+impl<S, B> Service<http::Request<B>> for ScopeFromExtensions<S>
+where
+    S: Service<http::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = SlogScope<Logger, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let logger = match req.extensions().get::<Logger>() {
+            Some(logger) => logger.clone(),
+            None => self.default.clone().unwrap_or_else(slog_scope::logger),
+        };
+
+        let inner = &mut self.inner;
+        let future = slog_scope::scope(&logger, || inner.call(req));
+
+        SlogScope::new(logger, future)
+    }
+}
+
+#[cfg(feature = "http")]
+pin_project! {
+    /// A response future scoping the response body as well.
+    ///
+    /// Once the response is ready, its body is wrapped in a `SlogScope` with
+    /// the same logger so that logs emitted while it's streamed keep the
+    /// request's context.
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: SlogScope<Logger, F>,
+    }
+}
+
+#[cfg(feature = "http")]
+impl<F> ResponseFuture<F> {
+    /// Scope a response future and the body of its response.
+    pub fn new(inner: SlogScope<Logger, F>) -> Self {
+        ResponseFuture { inner }
+    }
+}
+
+#[cfg(feature = "http")]
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<SlogScope<Logger, B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.project().inner;
+        let res = ready!(inner.as_mut().poll(cx));
+        let logger = inner.logger().clone();
+
+        Poll::Ready(res.map(|res| res.map(|body| SlogScope::new(logger, body))))
+    }
+}
@@ -0,0 +1,41 @@
+//! A scoped future can also enter a `tracing` span on every poll, keeping
+//! both context systems in sync:
+//!
+//! ```
+//! # use slog::o;
+//! use slog_scope_futures::FutureExt;
+//!
+//! let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+//!
+//! tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+//!     let span = tracing::info_span!("conn", id = 1);
+//!     let id = span.id();
+//!
+//!     let fut = async move {
+//!         assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(conn)");
+//!         assert_eq!(tracing::Span::current().id(), id);
+//!     };
+//!
+//!     futures_executor::block_on(fut.with_logger(logger).with_span(span));
+//! });
+//! ```
+
+use std::borrow::Borrow;
+
+use slog::Logger;
+use tracing::{instrument::Instrumented, Instrument, Span};
+
+use super::SlogScope;
+
+impl<L, F> SlogScope<L, F>
+where
+    L: Borrow<Logger>,
+{
+    /// Also enter `span` whenever the inner future is polled.
+    ///
+    /// The span is entered inside the slog scope, and dropped along with the
+    /// wrapper. Any enabled diagnostics are kept.
+    pub fn with_span(self, span: Span) -> SlogScope<L, Instrumented<F>> {
+        self.map_parts(|logger, inner| (logger, inner.instrument(span)))
+    }
+}
@@ -0,0 +1,84 @@
+//! Warp drives handler futures from inside its own combinators, so
+//! [`with_scope`] extracts a [`RequestScope`] holding the request's logger,
+//! which then wraps the handler future in one step:
+//!
+//! ```
+//! # use slog::o;
+//! # use std::sync::{atomic::{AtomicU64, Ordering}, Arc};
+//! use slog_scope_futures::warp::{with_scope, RequestScope};
+//! use warp::Filter;
+//!
+//! async fn hello(name: String) -> Result<String, warp::Rejection> {
+//!     Ok(format!("{} {:?}", name, slog_scope::logger()))
+//! }
+//!
+//! let root = slog::Logger::root(slog::Discard, o!());
+//! let ids = Arc::new(AtomicU64::new(0));
+//! let route = warp::path!("hello" / String)
+//!     .and(with_scope(move |method, path| {
+//!         root.new(o!(
+//!             "request_id" => ids.fetch_add(1, Ordering::Relaxed),
+//!             "method" => method.to_string(),
+//!             "path" => path.as_str().to_owned(),
+//!         ))
+//!     }))
+//!     .and_then(|name, scope: RequestScope| scope.run(hello(name)));
+//!
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! runtime.block_on(async {
+//!     let res = warp::test::request().path("/hello/world").reply(&route).await;
+//!     assert_eq!(res.body(), "world Logger(path, method, request_id)");
+//!
+//!     // Rejections from the filters around `with_scope` are left untouched.
+//!     let res = warp::test::request().path("/goodbye").reply(&route).await;
+//!     assert_eq!(res.status(), 404);
+//! });
+//! ```
+//!
+//! [`with_scope`]: crate::warp::with_scope
+//! [`RequestScope`]: crate::warp::RequestScope
+
+use std::{convert::Infallible, future::Future};
+
+use slog::Logger;
+use warp::{filters::path::FullPath, http::Method, Filter};
+
+use super::SlogScope;
+
+/// A filter extracting a [`RequestScope`] for each request.
+///
+/// `make_logger` derives the request's logger from its method and path.
+/// This filter never rejects.
+pub fn with_scope<M>(
+    make_logger: M,
+) -> impl Filter<Extract = (RequestScope,), Error = Infallible> + Clone
+where
+    M: Fn(&Method, &FullPath) -> Logger + Clone + Send + Sync + 'static,
+{
+    warp::method()
+        .and(warp::path::full())
+        .map(move |method, path| RequestScope {
+            logger: make_logger(&method, &path),
+        })
+}
+
+/// The logger of a single request, as extracted by [`with_scope`].
+#[derive(Clone, Debug)]
+pub struct RequestScope {
+    logger: Logger,
+}
+
+impl RequestScope {
+    /// The request's logger.
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Wrap a handler future in the request's scope.
+    pub fn run<F>(self, handler: F) -> SlogScope<Logger, F>
+    where
+        F: Future,
+    {
+        SlogScope::new(self.logger, handler)
+    }
+}
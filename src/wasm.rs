@@ -0,0 +1,41 @@
+//! On wasm, futures are spawned onto the JS event loop through
+//! `wasm-bindgen-futures`, and [`spawn_local_scoped`] keeps the scope they
+//! were spawned from:
+//!
+//! ```ignore
+//! use slog::o;
+//! use slog_scope_futures::wasm::spawn_local_scoped;
+//!
+//! let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+//!
+//! slog_scope::scope(&request, || {
+//!     spawn_local_scoped(async {
+//!         slog::info!(slog_scope::logger(), "still has request_id");
+//!     })
+//! });
+//! ```
+
+use std::{borrow::Borrow, future::Future};
+
+use slog::Logger;
+
+use super::SlogScope;
+
+/// Spawn a future onto the JS event loop, running in the current scope.
+///
+/// The current logger is captured when this is called.
+pub fn spawn_local_scoped<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    spawn_local_scoped_with(slog_scope::logger(), future)
+}
+
+/// Spawn a future onto the JS event loop, running in `logger`'s scope.
+pub fn spawn_local_scoped_with<L, F>(logger: L, future: F)
+where
+    L: Borrow<Logger> + 'static,
+    F: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(SlogScope::new(logger, future))
+}
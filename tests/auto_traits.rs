@@ -0,0 +1,53 @@
+//! Wrappers are `Send` and `Sync` exactly when what they wrap is, whatever
+//! diagnostics are enabled.
+
+use std::{
+    cell::Cell,
+    future::{self, Ready},
+    rc::Rc,
+    sync::Arc,
+};
+
+use slog::Logger;
+use slog_scope_futures::{
+    iter::ScopedIter, lazy::WithLoggerFn, optional::OptionalScope, SlogScope,
+};
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+type SendSync = Ready<()>;
+type SendOnly = Cell<()>;
+type Neither = Rc<()>;
+
+assert_impl_all!(SlogScope<Logger, SendSync>: Send, Sync, Unpin);
+assert_impl_all!(SlogScope<&'static Logger, SendSync>: Send, Sync);
+assert_impl_all!(SlogScope<Arc<Logger>, SendSync>: Send, Sync);
+
+assert_impl_all!(SlogScope<Logger, SendOnly>: Send);
+assert_not_impl_any!(SlogScope<Logger, SendOnly>: Sync);
+
+assert_not_impl_any!(SlogScope<Logger, Neither>: Send, Sync);
+assert_not_impl_any!(SlogScope<Rc<Logger>, SendSync>: Send, Sync);
+
+assert_impl_all!(OptionalScope<Logger, SendSync>: Send, Sync);
+assert_not_impl_any!(OptionalScope<Logger, Neither>: Send, Sync);
+assert_impl_all!(WithLoggerFn<fn() -> Logger, SendSync>: Send, Sync);
+assert_impl_all!(ScopedIter<Logger, std::vec::IntoIter<u8>>: Send, Sync);
+assert_not_impl_any!(ScopedIter<Logger, std::vec::IntoIter<Neither>>: Send, Sync);
+
+#[test]
+fn diagnostics_keep_the_wrapper_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let logger = Logger::root(slog::Discard, slog::o!());
+    let (scope, metrics) = SlogScope::new(logger, future::ready(()))
+        .log_panics()
+        .log_completion(slog::Level::Info, "done")
+        .log_cancellation(slog::Level::Info, "cancelled")
+        .slow_poll_threshold(std::time::Duration::from_millis(10))
+        .log_wake_counts(slog::Level::Debug)
+        .tag_task()
+        .instrumented();
+
+    assert_send_sync(&scope);
+    assert_send_sync(&metrics);
+}
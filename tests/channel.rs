@@ -0,0 +1,68 @@
+//! Messages are handled under the logger of the task that sent them.
+
+#![cfg(feature = "futures-channel")]
+
+use futures_executor::ThreadPool;
+use futures_util::StreamExt as _;
+use slog::{info, o};
+use slog_scope_futures::{
+    channel::{scoped_channel, scoped_unbounded},
+    futures03::SpawnExt,
+    test_util::capture,
+};
+
+#[test]
+fn bounded_messages_carry_their_producer_scope() {
+    let (logger, records) = capture();
+    let pool = ThreadPool::new().unwrap();
+    let (tx, rx) = scoped_channel::<u32>(1);
+
+    for producer in ["a", "b"] {
+        let mut tx = tx.clone();
+        let scope = logger.new(o!("producer" => producer));
+        pool.spawn_scoped_with(scope, async move {
+            for job in 0..3 {
+                tx.send(job).await.unwrap();
+            }
+        })
+        .unwrap();
+    }
+    drop(tx);
+
+    let consumer = rx.for_each_scoped(|job| {
+        info!(slog_scope::logger(), "handling"; "job" => job);
+        async move { info!(slog_scope::logger(), "handled"; "job" => job) }
+    });
+    futures_executor::block_on(consumer);
+
+    let records = records.records();
+    assert_eq!(records.len(), 12);
+    for producer in ["a", "b"] {
+        let count = records
+            .iter()
+            .filter(|r| r.kv("producer") == Some(producer))
+            .count();
+        assert_eq!(count, 6);
+    }
+}
+
+#[test]
+fn unbounded_receiver_yields_the_sender_logger() {
+    let (logger, records) = capture();
+    let (tx, rx) = scoped_unbounded::<&str>();
+
+    for producer in ["a", "b"] {
+        let scope = logger.new(o!("producer" => producer));
+        slog_scope::scope(&scope, || tx.unbounded_send(producer).unwrap());
+    }
+    drop(tx);
+
+    let received = futures_executor::block_on(rx.collect::<Vec<_>>());
+    for (logger, producer) in &received {
+        info!(logger, "received"; "from" => *producer);
+    }
+    assert_eq!(received.len(), 2);
+
+    let records = records.records();
+    assert!(records.iter().all(|r| r.kv("producer") == r.kv("from")));
+}
@@ -0,0 +1,62 @@
+//! Codecs log under the connection's logger while `Framed` drives them.
+
+#![cfg(feature = "tokio-util")]
+
+use bytes::BytesMut;
+use futures_util::StreamExt;
+use slog::{o, warn};
+use slog_scope_futures::{test_util::capture, tokio_util::CodecExt};
+use tokio_util::codec::{Decoder, FramedRead, LinesCodec, LinesCodecError};
+
+/// Newline-delimited numbers, skipping and warning about anything else.
+struct Numbers(LinesCodec);
+
+impl Decoder for Numbers {
+    type Item = u32;
+    type Error = LinesCodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<u32>, LinesCodecError> {
+        while let Some(line) = self.0.decode(src)? {
+            match line.parse() {
+                Ok(n) => return Ok(Some(n)),
+                Err(_) => warn!(slog_scope::logger(), "malformed frame"; "frame" => line),
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[test]
+fn corrupt_frames_are_logged_in_the_connection_scope() {
+    let (logger, records) = capture();
+    let conn = logger.new(o!("conn_id" => 3));
+
+    let io: &[u8] = b"1\nnope\n2\n";
+    let frames = FramedRead::new(io, Numbers(LinesCodec::new()).with_logger(&conn));
+    let frames: Vec<_> = futures_executor::block_on(frames.collect());
+
+    assert_eq!(
+        frames.into_iter().collect::<Result<Vec<_>, _>>().unwrap(),
+        [1, 2]
+    );
+
+    let records = records.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].msg(), "malformed frame");
+    assert_eq!(records[0].kv("frame"), Some("nope"));
+    assert_eq!(records[0].kv("conn_id"), Some("3"));
+}
+
+#[test]
+fn errors_are_passed_through() {
+    let conn = slog::Logger::root(slog::Discard, o!("conn_id" => 3));
+
+    let io: &[u8] = b"way too long\n";
+    let frames = FramedRead::new(io, LinesCodec::new_with_max_length(4).with_logger(&conn));
+    let frames: Vec<_> = futures_executor::block_on(frames.collect());
+
+    assert!(matches!(
+        frames[0],
+        Err(LinesCodecError::MaxLineLengthExceeded)
+    ));
+}
@@ -0,0 +1,30 @@
+//! The import paths of the last release keep working until they're removed.
+
+#![allow(deprecated)]
+
+use slog::o;
+
+#[test]
+fn future03_future_ext() {
+    use slog_scope_futures::future03::FutureExt;
+
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    let fut = async { format!("{:?}", slog_scope::logger()) }.with_logger(logger);
+
+    assert_eq!(futures_executor::block_on(fut), "Logger(request_id)");
+}
+
+#[cfg(feature = "futures-01")]
+#[test]
+fn future01_future_ext() {
+    use futures::Future;
+    use slog_scope_futures::future01::FutureExt;
+
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    let fut = futures::future::lazy(|| Ok::<_, ()>(format!("{:?}", slog_scope::logger())));
+
+    assert_eq!(
+        fut.with_logger(logger).wait(),
+        Ok("Logger(request_id)".to_owned())
+    );
+}
@@ -0,0 +1,77 @@
+//! The extension traits of every futures flavour can be imported together,
+//! and `with_logger` resolves the same way whatever's in scope.
+
+#![cfg(feature = "futures-01")]
+
+use std::{
+    future::Future as StdFuture,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Async, Poll as Poll01};
+use slog::o;
+
+/// A future for both `std::future` and futures 0.1.
+struct Both;
+
+impl StdFuture for Both {
+    type Output = &'static str;
+
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<&'static str> {
+        Poll::Ready("std")
+    }
+}
+
+impl futures::Future for Both {
+    type Item = &'static str;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll01<&'static str, ()> {
+        Ok(Async::Ready("0.1"))
+    }
+}
+
+// The globs are imported in each test, where they can't shadow `#[test]`
+// with the crate's own attribute.
+
+#[test]
+fn with_logger_picks_the_std_future() {
+    #[allow(unused_imports)]
+    use slog_scope_futures::{futures01::*, futures03::*, *};
+
+    let logger = slog::Logger::root(slog::Discard, o!());
+
+    let fut = Both.with_logger(&logger);
+    assert_eq!(futures_executor::block_on(fut), "std");
+}
+
+#[test]
+fn with_logger01_picks_the_futures01_future() {
+    use futures::Future as _;
+    #[allow(unused_imports)]
+    use slog_scope_futures::{futures01::*, futures03::*, *};
+
+    let logger = slog::Logger::root(slog::Discard, o!());
+
+    let fut = Both.with_logger01(&logger);
+    assert_eq!(fut.wait(), Ok("0.1"));
+}
+
+#[test]
+fn streams_resolve_the_same_way() {
+    use futures::{Future as _, Stream as _};
+    use futures_util::StreamExt as _;
+    use slog_scope_futures::{futures01::StreamExt01, futures03::StreamExt};
+
+    let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+
+    let items03 = futures_util::stream::iter(0..2).with_logger(&logger);
+    let items01 = futures::stream::iter_ok::<_, ()>(0..2).with_logger01(&logger);
+
+    assert_eq!(
+        futures_executor::block_on(items03.collect::<Vec<_>>()),
+        [0, 1]
+    );
+    assert_eq!(items01.collect().wait(), Ok(vec![0, 1]));
+}
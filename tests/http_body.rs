@@ -0,0 +1,47 @@
+//! Response bodies streamed under the request's logger.
+
+#![cfg(feature = "http-body")]
+
+use std::convert::Infallible;
+
+use bytes::Bytes;
+use futures_util::stream;
+use http_body::Frame;
+use http_body_util::{BodyExt, StreamBody};
+use slog::{info, o};
+use slog_scope_futures::{test_util::capture, SlogScope};
+
+#[test]
+fn channel_fed_body_logs_each_chunk_under_the_request_logger() {
+    let (logger, records) = capture();
+    let request = logger.new(o!("request_id" => 42, "path" => "/download"));
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<&'static str>(1);
+    let chunks = stream::unfold(rx, |mut rx| async move {
+        let chunk = rx.recv().await?;
+        info!(slog_scope::logger(), "sending chunk"; "len" => chunk.len());
+        Some((Ok::<_, Infallible>(Frame::data(Bytes::from(chunk))), rx))
+    });
+    let body = SlogScope::new(request, StreamBody::new(chunks));
+
+    let producer = std::thread::spawn(move || {
+        for chunk in ["hello", " ", "world"] {
+            tx.blocking_send(chunk).unwrap();
+        }
+    });
+    let body = futures_executor::block_on(BodyExt::collect(body))
+        .unwrap()
+        .to_bytes();
+    producer.join().unwrap();
+
+    assert_eq!(body, "hello world");
+
+    let records = records.records();
+    let lens: Vec<_> = records.iter().map(|r| r.kv("len").unwrap()).collect();
+    assert_eq!(lens, ["5", "1", "5"]);
+    for record in &records {
+        assert_eq!(record.msg(), "sending chunk");
+        assert_eq!(record.kv("request_id"), Some("42"));
+        assert_eq!(record.kv("path"), Some("/download"));
+    }
+}
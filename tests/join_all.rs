@@ -0,0 +1,68 @@
+//! Joined futures logging under their index, including when cancelled.
+
+use futures_util::future;
+use slog::{info, o};
+use slog_scope_futures::{
+    futures03::{scoped_join_all, scoped_try_join_all},
+    test_util::capture,
+};
+
+/// Logs when dropped, like a connection closing on cancellation.
+struct Conn;
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        info!(slog_scope::logger(), "conn dropped");
+    }
+}
+
+async fn shard(shard: u32) -> Result<u32, String> {
+    let _conn = Conn;
+    info!(slog_scope::logger(), "querying"; "shard" => shard);
+    match shard {
+        0 => future::pending().await,
+        1 => Err("shard 1 is down".to_owned()),
+        _ => Ok(shard),
+    }
+}
+
+#[test]
+fn failing_future_short_circuits_and_the_rest_drop_in_scope() {
+    let (logger, records) = capture();
+    let logger = logger.new(o!("job" => 1));
+
+    let out = futures_executor::block_on(scoped_try_join_all(&logger, (0..3).map(shard)));
+    assert_eq!(out, Err("shard 1 is down".to_owned()));
+
+    let records = records.records();
+    let queries: Vec<_> = records
+        .iter()
+        .filter(|r| r.msg() == "querying")
+        .map(|r| (r.kv("shard").unwrap(), r.kv("index").unwrap()))
+        .collect();
+    // Once shard 1 failed, the last one isn't even started.
+    assert_eq!(queries, [("0", "0"), ("1", "1")]);
+
+    // Shard 0 is cancelled after shard 1's connection closes.
+    let dropped: Vec<_> = records
+        .iter()
+        .filter(|r| r.msg() == "conn dropped")
+        .map(|r| r.kv("index").unwrap())
+        .collect();
+    assert_eq!(dropped, ["1", "0"]);
+    assert!(records.iter().all(|r| r.kv("job") == Some("1")));
+}
+
+#[test]
+fn successful_futures_are_joined_in_order() {
+    let (logger, records) = capture();
+
+    let out = futures_executor::block_on(scoped_try_join_all(&logger, [shard(2), shard(3)]));
+    assert_eq!(out, Ok(vec![2, 3]));
+
+    let out = futures_executor::block_on(scoped_join_all(logger, (1..3).map(shard)));
+    assert_eq!(out, [Err("shard 1 is down".to_owned()), Ok(2)]);
+
+    assert!(records.contains_kv("index", "1"));
+    assert_eq!(records.len(), 8);
+}
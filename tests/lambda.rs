@@ -0,0 +1,65 @@
+//! Lambda invocations log under their own logger, and everything else under
+//! the base logger.
+
+#![cfg(feature = "lambda")]
+
+use std::sync::Arc;
+
+use lambda_runtime::{Context, Error, LambdaEvent, Service};
+use slog::{info, o};
+use slog_scope_futures::{lambda::scoped_handler, test_util::capture};
+
+async fn handler(event: u32, _: Context) -> Result<u32, Error> {
+    info!(slog_scope::logger(), "handling"; "event" => event);
+    Ok(event + 1)
+}
+
+fn context(request_id: &str) -> Context {
+    let mut context = Context::default();
+    context.request_id = request_id.into();
+    context.invoked_function_arn = "arn:aws:lambda:eu-west-1:123:function:billing".into();
+    context.env_config = Arc::new(lambda_runtime::Config {
+        function_name: "billing".into(),
+        version: "$LATEST".into(),
+        ..Default::default()
+    });
+    context
+}
+
+#[test]
+fn invocations_log_under_their_request_id() {
+    let (logger, records) = capture();
+    let base = logger.new(o!("app" => "billing"));
+    let mut service = scoped_handler(base.clone(), handler);
+
+    info!(base, "cold start");
+    futures_executor::block_on(async {
+        assert_eq!(
+            service
+                .call(LambdaEvent::new(1, context("a1")))
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            service
+                .call(LambdaEvent::new(2, context("b2")))
+                .await
+                .unwrap(),
+            3
+        );
+    });
+
+    let records = records.records();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].msg(), "cold start");
+    assert_eq!(records[0].kv("aws_request_id"), None);
+
+    assert_eq!(records[1].kv("aws_request_id"), Some("a1"));
+    assert_eq!(records[2].kv("aws_request_id"), Some("b2"));
+    for record in &records[1..] {
+        assert_eq!(record.kv("app"), Some("billing"));
+        assert_eq!(record.kv("function_name"), Some("billing"));
+        assert_eq!(record.kv("function_version"), Some("$LATEST"));
+    }
+}
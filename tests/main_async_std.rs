@@ -0,0 +1,28 @@
+//! `#[slog_scope_futures::main]` runs `main` on a async-std runtime under the
+//! global logger it installs.
+
+use std::sync::OnceLock;
+
+use slog::{info, o};
+use slog_scope_futures::test_util::CapturingDrain;
+
+fn records() -> &'static CapturingDrain {
+    static RECORDS: OnceLock<CapturingDrain> = OnceLock::new();
+    RECORDS.get_or_init(CapturingDrain::new)
+}
+
+#[slog_scope_futures::main(records().logger().new(o!("app" => "test")), runtime = "async-std")]
+async fn main() {
+    info!(slog_scope::logger(), "in main");
+    async_std::task::spawn(async { info!(slog_scope::logger(), "in a task") }).await;
+    // Threads outside any scope fall back to the global logger.
+    std::thread::spawn(|| info!(slog_scope::logger(), "in a thread"))
+        .join()
+        .unwrap();
+
+    let records = records().records();
+    let msgs: Vec<_> = records.iter().map(|r| r.msg()).collect();
+    assert_eq!(msgs, ["in main", "in a task", "in a thread"]);
+    assert!(records.iter().all(|r| r.kv("app") == Some("test")));
+    println!("main_async_std: ok");
+}
@@ -0,0 +1,97 @@
+//! Each connection to a server runs under a logger of its own, and its
+//! requests under children of it.
+
+#![cfg(all(feature = "tower", feature = "http", not(target_arch = "wasm32")))]
+
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::{
+    extract::Request,
+    middleware::{self, Next},
+    response::Response,
+    routing::get,
+    serve::IncomingStream,
+    Router,
+};
+use slog::{info, o};
+use slog_scope_futures::{
+    test_util::capture,
+    tower::{ScopeFromExtensionsLayer, ScopedMakeService},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+async fn handler() -> &'static str {
+    info!(slog_scope::logger(), "handled");
+    "ok"
+}
+
+/// Derive the request's logger from the current one, the connection's.
+async fn request_logger(mut req: Request, next: Next) -> Response {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+
+    let request_id = NEXT.fetch_add(1, Ordering::Relaxed);
+    let logger = slog_scope::logger().new(o!("request_id" => request_id));
+    req.extensions_mut().insert(logger);
+
+    next.run(req).await
+}
+
+async fn get_twice(addr: std::net::SocketAddr) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    for _ in 0..2 {
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut buf = [0; 1024];
+        let mut response = Vec::new();
+        while !response.ends_with(b"ok") {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0, "connection closed early");
+            response.extend_from_slice(&buf[..n]);
+        }
+    }
+}
+
+#[tokio::test]
+async fn connections_get_loggers_of_their_own() {
+    let (root, records) = capture();
+
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(ScopeFromExtensionsLayer::new())
+        .layer(middleware::from_fn(request_logger));
+
+    let next_conn = AtomicU64::new(0);
+    let make_service =
+        ScopedMakeService::new(app, move |conn: &IncomingStream<'_, TcpListener>| {
+            let conn_id = next_conn.fetch_add(1, Ordering::Relaxed);
+            root.new(o!("conn_id" => conn_id, "peer_addr" => conn.remote_addr().to_string()))
+        });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, make_service).await.unwrap() });
+
+    tokio::join!(get_twice(addr), get_twice(addr));
+
+    let records = records.records();
+    assert_eq!(records.len(), 4);
+    assert!(records.iter().all(|r| r.msg() == "handled"));
+    assert!(records.iter().all(|r| r.kv("peer_addr").is_some()));
+
+    let conns: HashSet<_> = records.iter().map(|r| r.kv("conn_id").unwrap()).collect();
+    assert_eq!(conns.len(), 2);
+    let requests: HashSet<_> = records
+        .iter()
+        .map(|r| r.kv("request_id").unwrap())
+        .collect();
+    assert_eq!(requests.len(), 4);
+}
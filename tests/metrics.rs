@@ -0,0 +1,32 @@
+//! Poll metrics read through a `MetricsHandle`.
+
+use std::future;
+
+use slog::o;
+use slog_scope_futures::{test_util::ManualExecutor, FutureExt};
+
+#[test]
+fn polling_a_clone_leaves_the_original_handle_alone() {
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    let (fut, metrics) = future::ready(()).with_logger(logger).instrumented();
+
+    ManualExecutor::new(fut.clone()).run();
+    assert_eq!(metrics.polls(), 0);
+    assert!(!metrics.completed());
+    assert_eq!(metrics.time_to_completion(), None);
+
+    ManualExecutor::new(fut).run();
+    assert_eq!(metrics.polls(), 1);
+    assert!(metrics.completed());
+}
+
+#[test]
+fn a_clone_can_be_instrumented_on_its_own() {
+    let logger = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    let (fut, metrics) = future::ready(()).with_logger(logger).instrumented();
+
+    let (clone, clone_metrics) = fut.clone().instrumented();
+    ManualExecutor::new(clone).run();
+    assert_eq!(clone_metrics.polls(), 1);
+    assert_eq!(metrics.polls(), 0);
+}
@@ -0,0 +1,67 @@
+//! The poll-statistics record logged when a wrapped future completes.
+
+use std::{future, thread, time::Duration};
+
+use slog::Level;
+use slog_scope_futures::{
+    test_util::{capture, CapturedRecord, ManualExecutor, ScopeProbe},
+    FutureExt, SlogScope,
+};
+
+fn micros(record: &CapturedRecord, key: &str) -> u128 {
+    record.kv(key).unwrap().parse().unwrap()
+}
+
+#[test]
+fn stats_are_logged_once_on_completion() {
+    let (logger, records) = capture();
+    let probe = ScopeProbe::new();
+
+    let fut = async {
+        thread::sleep(Duration::from_millis(2));
+        probe.future(2).await
+    };
+    ManualExecutor::new(
+        fut.with_logger(&logger)
+            .log_poll_stats(Level::Info, "request stats"),
+    )
+    .run();
+
+    let records = records.records();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.msg(), "request stats");
+    assert_eq!(record.kv("polls"), Some("3"));
+    for key in ["busy_us", "idle_us", "max_poll_us", "total_ms"] {
+        assert!(record.kv(key).is_some(), "missing {}", key);
+    }
+    assert!(micros(record, "max_poll_us") >= 2_000);
+    assert!(micros(record, "max_poll_us") <= micros(record, "busy_us"));
+}
+
+#[test]
+fn builder_enables_stats() {
+    let (logger, records) = capture();
+
+    let fut = SlogScope::builder(logger)
+        .log_poll_stats(Level::Debug, "stats")
+        .build(future::ready(()));
+    ManualExecutor::new(fut).run();
+
+    assert_eq!(records.len(), 1);
+    assert!(records.contains_kv("polls", "1"));
+}
+
+#[test]
+fn nothing_is_logged_when_cancelled() {
+    let (logger, records) = capture();
+
+    let fut = future::pending::<()>()
+        .with_logger(&logger)
+        .log_poll_stats(Level::Info, "request stats");
+    let mut executor = ManualExecutor::new(fut);
+    assert!(executor.poll().is_pending());
+    drop(executor);
+
+    assert_eq!(records.len(), 0);
+}
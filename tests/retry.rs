@@ -0,0 +1,39 @@
+//! Backoff between retried attempts.
+
+use std::{cell::RefCell, time::Duration};
+
+use slog_scope_futures::{
+    retry::{retry_scoped_with, RetryPolicy},
+    test_util::capture,
+};
+
+fn backoffs(attempts: u32, initial: Duration, max: Duration) -> Vec<Duration> {
+    let (logger, _records) = capture();
+    let slept = RefCell::new(Vec::new());
+
+    let policy = RetryPolicy::new(attempts, |backoff| {
+        slept.borrow_mut().push(backoff);
+        async {}
+    })
+    .with_backoff(initial, max);
+    let out = retry_scoped_with(logger, || async { Err::<(), _>("refused") }, policy);
+    assert_eq!(futures_executor::block_on(out), Err("refused"));
+
+    slept.into_inner()
+}
+
+#[test]
+fn backoff_saturates_instead_of_overflowing() {
+    let slept = backoffs(100, Duration::from_secs(1), Duration::MAX);
+
+    assert_eq!(slept.len(), 99);
+    assert_eq!(slept[1], Duration::from_secs(2));
+    assert_eq!(slept.last(), Some(&Duration::MAX));
+}
+
+#[test]
+fn initial_backoff_is_clamped_to_the_max() {
+    let slept = backoffs(3, Duration::from_secs(10), Duration::from_secs(1));
+
+    assert_eq!(slept, [Duration::from_secs(1); 2]);
+}
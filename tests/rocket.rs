@@ -0,0 +1,71 @@
+//! Rocket handlers log under the logger of their request.
+
+#![cfg(feature = "rocket")]
+
+use rocket::{get, local::asynchronous::Client, routes};
+use slog::{info, o};
+use slog_scope_futures::{
+    rocket::{scope_routes, scoped, ScopeFairing, ScopedLogger},
+    test_util::capture,
+};
+
+#[get("/users/<id>")]
+async fn user(id: u32) -> &'static str {
+    info!(slog_scope::logger(), "looking up user"; "user" => id);
+    "ok"
+}
+
+#[get("/explicit")]
+async fn explicit(logger: ScopedLogger) -> &'static str {
+    info!(logger.0, "explicit");
+    "ok"
+}
+
+#[rocket::async_test]
+async fn handler_logs_carry_the_request_context() {
+    let (logger, records) = capture();
+    let rocket = rocket::build()
+        .attach(ScopeFairing::new(logger.new(o!("service" => "api"))))
+        .mount("/", scope_routes(routes![user]))
+        .mount("/", routes![explicit]);
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let first = client.get("/users/1").dispatch().await;
+    let second = client.get("/users/2").dispatch().await;
+    client.get("/explicit").dispatch().await;
+
+    let records = records.records();
+    let records: Vec<_> = records
+        .iter()
+        .filter(|r| r.kv("service").is_some())
+        .collect();
+    assert_eq!(records.len(), 3);
+
+    assert_eq!(records[0].msg(), "looking up user");
+    assert_eq!(records[0].kv("method"), Some("GET"));
+    assert_eq!(records[0].kv("uri"), Some("/users/1"));
+    assert_eq!(records[1].kv("uri"), Some("/users/2"));
+    assert_eq!(records[2].kv("uri"), Some("/explicit"));
+
+    let ids: Vec<_> = records
+        .iter()
+        .map(|r| r.kv("request_id").unwrap())
+        .collect();
+    assert!(ids[0] != ids[1] && ids[1] != ids[2]);
+    assert_eq!(first.headers().get_one("x-request-id"), Some(ids[0]));
+    assert_eq!(second.headers().get_one("x-request-id"), Some(ids[1]));
+}
+
+#[rocket::async_test]
+async fn without_the_fairing_the_current_scope_is_kept() {
+    let rocket = rocket::build().mount("/", scope_routes(routes![explicit]));
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let outer = slog::Logger::root(slog::Discard, o!("outer" => 1));
+    let req = client.get("/explicit");
+    let scope = slog_scope::scope(&outer, || {
+        scoped(req.inner(), async { format!("{:?}", slog_scope::logger()) })
+    });
+
+    assert_eq!(scope.await, "Logger(outer)");
+}
@@ -0,0 +1,260 @@
+//! The scope is active while the wrapped value is polled, and only then.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    task::Poll,
+};
+
+use futures_util::future;
+use slog::{info, o};
+use slog_scope_futures::{
+    futures03::StreamExt,
+    iter::IteratorExt,
+    poll_fn_scoped,
+    test_util::{active_kvs, capture, logger_kvs, ManualExecutor, ScopeProbe},
+    FutureExt,
+};
+
+fn logger(kv: &'static str) -> slog::Logger {
+    slog::Logger::root(slog::Discard, o!(kv => 1))
+}
+
+#[test]
+fn future_is_polled_in_scope_only() {
+    let logger = logger("request_id");
+    let probe = ScopeProbe::new();
+
+    let mut executor = ManualExecutor::new(probe.future(3).with_logger(&logger));
+    while executor.poll().is_pending() {
+        assert!(active_kvs().is_empty());
+    }
+    assert!(active_kvs().is_empty());
+
+    assert_eq!(probe.polls(), 4);
+    assert!(probe.always_in(&logger));
+}
+
+#[test]
+fn outer_scope_is_restored_between_polls() {
+    let outer = logger("outer");
+    let inner = logger("inner");
+    let probe = ScopeProbe::new();
+
+    let mut executor = ManualExecutor::new(probe.future(2).with_logger(&inner));
+    slog_scope::scope(&outer, || {
+        while executor.poll().is_pending() {
+            assert_eq!(active_kvs(), logger_kvs(&outer));
+        }
+    });
+
+    assert!(probe.always_in(&inner));
+}
+
+#[test]
+fn nested_wrappers_use_the_innermost_logger() {
+    let outer = logger("outer");
+    let inner = outer.new(o!("inner" => 1));
+    let probe = ScopeProbe::new();
+
+    let fut = probe.future(2).with_logger(&inner).with_logger(&outer);
+    ManualExecutor::new(fut).run();
+
+    assert!(probe.always_in(&inner));
+}
+
+#[test]
+fn stream_is_polled_in_scope_only() {
+    let logger = logger("conn");
+    let probe = ScopeProbe::new();
+
+    let mut executor = ManualExecutor::new(probe.stream(3).with_logger(&logger));
+    let mut items = Vec::new();
+    loop {
+        let next = executor.poll_next();
+        assert!(active_kvs().is_empty());
+        match next {
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => break,
+            Poll::Pending => {}
+        }
+    }
+
+    assert_eq!(items, [0, 1, 2]);
+    assert_eq!(probe.polls(), 7);
+    assert!(probe.always_in(&logger));
+}
+
+#[test]
+fn with_kv_keeps_the_scope_of_its_first_poll() {
+    let first = logger("first");
+    let second = logger("second");
+    let probe = ScopeProbe::new();
+
+    let mut executor = ManualExecutor::new(probe.future(1).with_kv(o!("step" => 1)));
+    slog_scope::scope(&first, || assert!(executor.poll().is_pending()));
+    slog_scope::scope(&second, || assert!(executor.poll().is_ready()));
+
+    assert!(probe.always_in(&first.new(o!("step" => 1))));
+}
+
+#[test]
+fn inherit_kv_follows_the_scope_of_each_poll() {
+    let first = logger("first");
+    let second = logger("second");
+    let probe = ScopeProbe::new();
+
+    let mut executor = ManualExecutor::new(probe.future(1).inherit_kv(o!("step" => 1)));
+    slog_scope::scope(&first, || assert!(executor.poll().is_pending()));
+    slog_scope::scope(&second, || assert!(executor.poll().is_ready()));
+
+    assert_eq!(
+        probe.scopes(),
+        [
+            logger_kvs(&first.new(o!("step" => 1))),
+            logger_kvs(&second.new(o!("step" => 1))),
+        ],
+    );
+}
+
+#[test]
+fn inherit_kv_follows_loggers_created_in_the_same_place() {
+    let (root, records) = capture();
+
+    let mut polls = 0;
+    let fut = future::poll_fn(|_| {
+        polls += 1;
+        info!(slog_scope::logger(), "polled");
+        if polls < 6 {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    });
+    let mut executor = ManualExecutor::new(fut.inherit_kv(o!("component" => "db")));
+    for id in 0..3 {
+        let request = root.new(o!("request_id" => id));
+        slog_scope::scope(&request, || {
+            assert!(executor.poll().is_pending());
+            assert_eq!(executor.poll().is_ready(), id == 2);
+        });
+    }
+
+    let ids: Vec<_> = records
+        .records()
+        .iter()
+        .map(|r| {
+            assert_eq!(r.kv("component"), Some("db"));
+            r.kv("request_id").unwrap().to_owned()
+        })
+        .collect();
+    assert_eq!(ids, ["0", "0", "1", "1", "2", "2"]);
+}
+
+#[test]
+fn missing_optional_logger_leaves_the_scope_alone() {
+    let outer = logger("outer");
+    let probe = ScopeProbe::new();
+
+    let fut = probe.future(1).with_optional_logger(None::<slog::Logger>);
+    slog_scope::scope(&outer, || ManualExecutor::new(fut).run());
+
+    assert!(probe.always_in(&outer));
+}
+
+#[test]
+fn scope_is_left_when_a_poll_panics() {
+    let logger = logger("request_id");
+    let fut = future::poll_fn(|_| -> Poll<()> { panic!("oh no") }).with_logger(&logger);
+
+    let mut executor = ManualExecutor::new(fut);
+    let out = panic::catch_unwind(AssertUnwindSafe(|| executor.poll()));
+
+    assert!(out.is_err());
+    assert!(active_kvs().is_empty());
+}
+
+#[test]
+fn iterator_steps_run_in_scope_only() {
+    let logger = logger("batch");
+
+    let mut iter = (0..3).map(|i| (i, active_kvs())).with_logger(&logger);
+    assert_eq!(iter.size_hint(), (3, Some(3)));
+    assert_eq!(iter.len(), 3);
+
+    assert_eq!(iter.next(), Some((0, logger_kvs(&logger))));
+    assert!(active_kvs().is_empty());
+    assert_eq!(iter.next_back(), Some((2, logger_kvs(&logger))));
+    assert_eq!(iter.size_hint(), (1, Some(1)));
+
+    let rest: Vec<_> = iter.collect();
+    assert_eq!(rest, [(1, logger_kvs(&logger))]);
+}
+
+#[test]
+fn iterator_fold_runs_downstream_closures_in_scope() {
+    let logger = logger("batch");
+
+    let scopes: Vec<_> =
+        (0..2)
+            .with_logger(&logger)
+            .map(|_| active_kvs())
+            .fold(Vec::new(), |mut scopes, scope| {
+                scopes.push(scope);
+                scopes
+            });
+
+    assert_eq!(scopes, [logger_kvs(&logger), logger_kvs(&logger)]);
+}
+
+#[test]
+fn poll_fn_is_called_in_scope_on_every_poll() {
+    let (logger, records) = capture();
+    let logger = logger.new(o!("conn" => 1));
+
+    let mut calls = 0;
+    let handshake = poll_fn_scoped(&logger, |_| {
+        calls += 1;
+        info!(slog_scope::logger(), "handshake step"; "call" => calls);
+        if calls < 3 {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    });
+
+    let mut executor = ManualExecutor::new(handshake);
+    while executor.poll().is_pending() {
+        assert!(active_kvs().is_empty());
+    }
+
+    let records = records.records();
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().all(|r| r.kv("conn") == Some("1")));
+}
+
+#[test]
+fn stream_poll_fn_is_called_in_scope_on_every_poll() {
+    let logger = logger("conn");
+
+    let mut polls = 0;
+    let frames = slog_scope_futures::futures03::poll_fn_scoped(logger.clone(), |_| {
+        polls += 1;
+        match polls {
+            1 | 3 => Poll::Pending,
+            2 | 4 => Poll::Ready(Some(active_kvs())),
+            _ => Poll::Ready(None),
+        }
+    });
+
+    let mut executor = ManualExecutor::new(frames);
+    let mut frames = Vec::new();
+    loop {
+        match executor.poll_next() {
+            Poll::Ready(Some(frame)) => frames.push(frame),
+            Poll::Ready(None) => break,
+            Poll::Pending => assert!(active_kvs().is_empty()),
+        }
+    }
+
+    assert_eq!(frames, [logger_kvs(&logger), logger_kvs(&logger)]);
+}
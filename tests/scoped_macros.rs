@@ -0,0 +1,128 @@
+//! Each branch or arm of the scoped combinator macros runs under its own logger.
+
+#![cfg(feature = "macros")]
+
+use std::task::Poll;
+
+use futures_util::{future, FutureExt as _};
+use slog::{info, o};
+use slog_scope_futures::{
+    scoped_join, scoped_select, scoped_try_join,
+    test_util::{capture, CapturingDrain},
+};
+
+async fn fetch(source: &'static str) -> &'static str {
+    info!(slog_scope::logger(), "fetching"; "source" => source);
+    source
+}
+
+#[slog_scope_futures::test]
+async fn join_branches_log_their_branch(records: CapturingDrain) {
+    let out = scoped_join!(slog_scope::logger();
+        "db" => fetch("db"),
+        "cache" => fetch("cache"),
+        o!("branch" => "api", "endpoint" => "/users") => fetch("api"),
+    );
+    assert_eq!(out, ("db", "cache", "api"));
+
+    let records = records.records();
+    assert_eq!(records.len(), 3);
+    for record in &records {
+        assert_eq!(record.kv("branch"), record.kv("source"));
+    }
+    let api = records.iter().find(|r| r.kv("branch") == Some("api"));
+    assert_eq!(api.unwrap().kv("endpoint"), Some("/users"));
+}
+
+#[slog_scope_futures::test]
+async fn try_join_branches_log_their_branch(records: CapturingDrain) {
+    let out = scoped_try_join!(&slog_scope::logger();
+        "db" => async { Ok::<_, ()>(fetch("db").await) },
+        "cache" => async { Ok(fetch("cache").await) },
+    );
+    assert_eq!(out, Ok(("db", "cache")));
+
+    let records = records.records();
+    assert_eq!(records.len(), 2);
+    for record in &records {
+        assert_eq!(record.kv("branch"), record.kv("source"));
+    }
+}
+
+/// Logs each time it's polled, completing on its `ready`-th poll.
+fn racer(
+    name: &'static str,
+    ready: u32,
+) -> impl std::future::Future<Output = &'static str> + Unpin {
+    let mut polls = 0;
+    future::poll_fn(move |cx| {
+        polls += 1;
+        info!(slog_scope::logger(), "polled"; "racer" => name);
+        if polls < ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        Poll::Ready(name)
+    })
+}
+
+#[test]
+fn select_arms_poll_and_run_in_their_scope() {
+    let (logger, records) = capture();
+    let fast = logger.new(o!("arm" => "fast"));
+    let slow = logger.new(o!("arm" => "slow"));
+
+    let mut finished = Vec::new();
+    futures_executor::block_on(async {
+        let mut a = racer("fast", 1).fuse();
+        let mut b = racer("slow", 3).fuse();
+        loop {
+            let winner = scoped_select! {
+                &fast, name = &mut a => {
+                    info!(slog_scope::logger(), "won"; "racer" => name);
+                    name
+                },
+                slow.clone(), name = &mut b => {
+                    info!(slog_scope::logger(), "won"; "racer" => name);
+                    name
+                },
+                complete => break,
+            };
+            finished.push(winner);
+        }
+    });
+    assert_eq!(finished, ["fast", "slow"]);
+
+    let records = records.records();
+    assert!(records.iter().filter(|r| r.msg() == "polled").count() >= 4);
+    assert_eq!(records.iter().filter(|r| r.msg() == "won").count(), 2);
+    for record in &records {
+        assert_eq!(record.kv("arm"), record.kv("racer"));
+    }
+}
+
+#[test]
+fn select_default_runs_outside_the_arm_scopes() {
+    let (logger, records) = capture();
+    let arm = logger.new(o!("arm" => "pending"));
+
+    let fell_through = slog_scope::scope(&logger, || {
+        futures_executor::block_on(async {
+            scoped_select! {
+                &arm, _ = racer("pending", 2).fuse() => false,
+                default => {
+                    info!(slog_scope::logger(), "nothing ready");
+                    true
+                }
+            }
+        })
+    });
+    assert!(fell_through);
+
+    let records = records.records();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].msg(), "polled");
+    assert_eq!(records[0].kv("arm"), Some("pending"));
+    assert_eq!(records[1].msg(), "nothing ready");
+    assert_eq!(records[1].kv("arm"), None);
+}
@@ -0,0 +1,39 @@
+//! Tasks spawned from one `SharedLogger` all log under the same logger.
+
+use slog::{info, o};
+use slog_scope_futures::{shared::SharedLogger, test_util::CapturingDrain, FutureExt};
+
+#[slog_scope_futures::test]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn spawned_tasks_carry_the_shared_kvs(records: CapturingDrain) {
+    let shared = SharedLogger::from(slog_scope::logger().new(o!("request_id" => 7)));
+
+    let tasks: Vec<_> = (0..32)
+        .map(|i| {
+            tokio::spawn(
+                async move {
+                    tokio::task::yield_now().await;
+                    info!(slog_scope::logger(), "sub-request"; "i" => i);
+                }
+                .with_shared_logger(&shared),
+            )
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let records = records.records();
+    assert_eq!(records.len(), 32);
+    assert!(records.iter().all(|r| r.kv("request_id") == Some("7")));
+}
+
+#[test]
+fn clones_share_one_logger() {
+    let shared = SharedLogger::new(slog::Logger::root(slog::Discard, o!()));
+    let other = shared.clone();
+
+    let a: &slog::Logger = &shared;
+    let b: &slog::Logger = &other;
+    assert!(std::ptr::eq(a, b));
+}
@@ -0,0 +1,245 @@
+//! Stream items processed under loggers of their own, and summaries of whole streams.
+
+use std::{cell::Cell, task::Poll};
+
+use futures_util::{
+    future,
+    stream::{self, StreamExt as _},
+};
+use slog::{info, o, Level};
+use slog_scope_futures::{
+    futures03::{ScopedFuturesUnordered, StreamExt},
+    test_util::{capture, CapturedRecord},
+};
+
+struct Job {
+    id: u32,
+}
+
+/// Completes once all three jobs have started.
+async fn all_started(started: &Cell<u32>) {
+    started.set(started.get() + 1);
+    future::poll_fn(|cx| {
+        if started.get() == 3 {
+            return Poll::Ready(());
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+async fn process(job: Job, started: &Cell<u32>) {
+    info!(slog_scope::logger(), "processing");
+    all_started(started).await;
+    info!(slog_scope::logger(), "done"; "job" => job.id);
+}
+
+#[test]
+fn concurrent_items_log_under_their_own_logger() {
+    let (logger, records) = capture();
+    let started = Cell::new(0);
+
+    let jobs = stream::iter((1..=3).map(|id| Job { id }))
+        .scope_items(|job| logger.new(o!("job_id" => job.id)))
+        .for_each_concurrent(None, |job| job.scope(|job| process(job, &started)));
+    futures_executor::block_on(jobs);
+
+    let records = records.records();
+    let msgs: Vec<_> = records.iter().map(|r| r.msg()).collect();
+    assert_eq!(
+        msgs,
+        [
+            "processing",
+            "processing",
+            "processing",
+            "done",
+            "done",
+            "done"
+        ]
+    );
+    for record in &records {
+        if let Some(job) = record.kv("job") {
+            assert_eq!(record.kv("job_id"), Some(job));
+        }
+    }
+    for id in ["1", "2", "3"] {
+        let count = records
+            .iter()
+            .filter(|r| r.kv("job_id") == Some(id))
+            .count();
+        assert_eq!(count, 2);
+    }
+}
+
+#[test]
+fn items_are_processed_under_their_index() {
+    let (logger, records) = capture();
+
+    let rows = stream::iter(["a", "b", "c"])
+        .with_logger(logger)
+        .with_item_index("item_index")
+        .for_each(|row| {
+            row.scope(|row| async move {
+                info!(slog_scope::logger(), "loaded"; "row" => row);
+                info!(slog_scope::logger(), "stored"; "row" => row);
+            })
+        });
+    futures_executor::block_on(rows);
+
+    let indices: Vec<_> = records
+        .records()
+        .iter()
+        .map(|r| {
+            (
+                r.kv("row").unwrap().to_owned(),
+                r.kv("item_index").unwrap().to_owned(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        indices,
+        [
+            ("a", "0"),
+            ("a", "0"),
+            ("b", "1"),
+            ("b", "1"),
+            ("c", "2"),
+            ("c", "2")
+        ]
+        .map(|(row, index)| (row.to_owned(), index.to_owned())),
+    );
+}
+
+#[test]
+fn summary_is_logged_once_when_the_stream_ends() {
+    let (logger, records) = capture();
+
+    let mut items = stream::iter(0..3)
+        .fuse()
+        .with_logger(logger)
+        .log_summary(Level::Info, "consumer finished");
+    futures_executor::block_on(async {
+        while items.next().await.is_some() {}
+        assert_eq!(items.next().await, None);
+        assert_eq!(items.next().await, None);
+    });
+    drop(items);
+
+    let records = records.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].msg(), "consumer finished");
+    assert_eq!(records[0].kv("items"), Some("3"));
+    assert_eq!(records[0].kv("completed"), Some("true"));
+    assert!(records[0].kv("elapsed_ms").is_some());
+}
+
+#[test]
+fn summary_is_logged_when_dropped_early() {
+    let (logger, records) = capture();
+
+    let mut items = stream::iter(0..3)
+        .with_logger(logger)
+        .log_summary(Level::Info, "consumer finished");
+    futures_executor::block_on(async {
+        items.next().await;
+        items.next().await;
+    });
+    drop(items);
+
+    let records = records.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].kv("items"), Some("2"));
+    assert_eq!(records[0].kv("completed"), Some("false"));
+}
+
+#[test]
+fn futures_unordered_gives_each_future_an_idx() {
+    let (logger, records) = capture();
+    let mut requests = ScopedFuturesUnordered::new(logger.new(o!("request_id" => 1)));
+
+    for i in 0..5 {
+        requests.push_scoped(async move {
+            info!(slog_scope::logger(), "sub-request"; "i" => i);
+        });
+    }
+    futures_executor::block_on(requests.collect::<Vec<_>>());
+
+    let records = records.records();
+    let mut idx: Vec<_> = records.iter().map(|r| r.kv("idx").unwrap()).collect();
+    idx.sort_unstable();
+    assert_eq!(idx, ["0", "1", "2", "3", "4"]);
+    assert!(records.iter().all(|r| r.kv("request_id") == Some("1")));
+}
+
+#[test]
+fn futures_unordered_of_send_futures_is_send() {
+    fn assert_send<T: Send>(_: T) {}
+
+    let mut requests = ScopedFuturesUnordered::new(slog::Logger::root(slog::Discard, o!()));
+    requests.push_scoped(async {});
+    assert_send(requests);
+}
+
+async fn process_one(job: Job, running: &Cell<u32>, max_running: &Cell<u32>) {
+    running.set(running.get() + 1);
+    max_running.set(max_running.get().max(running.get()));
+    info!(slog_scope::logger(), "processing");
+    yield_now().await;
+    info!(slog_scope::logger(), "done"; "job" => job.id);
+    running.set(running.get() - 1);
+}
+
+#[test]
+fn for_each_concurrent_scoped_runs_items_like_upstream() {
+    let (logger, records) = capture();
+    let (running, max_running) = (Cell::new(0), Cell::new(0));
+
+    let jobs = stream::iter((1..=5).map(|id| Job { id })).for_each_concurrent_scoped(
+        2,
+        |job| logger.new(o!("job_id" => job.id)),
+        |job| process_one(job, &running, &max_running),
+    );
+    futures_executor::block_on(jobs);
+    assert_eq!(max_running.get(), 2);
+
+    let (upstream_logger, upstream) = capture();
+    let jobs = stream::iter((1..=5).map(|id| Job { id }))
+        .scope_items(|job| upstream_logger.new(o!("job_id" => job.id)))
+        .for_each_concurrent(2, |job| {
+            job.scope(|job| process_one(job, &running, &max_running))
+        });
+    futures_executor::block_on(jobs);
+    assert_eq!(max_running.get(), 2);
+
+    let order = |records: &[CapturedRecord]| -> Vec<(String, String)> {
+        records
+            .iter()
+            .map(|r| (r.msg().to_owned(), r.kv("job_id").unwrap().to_owned()))
+            .collect()
+    };
+    let records = records.records();
+    assert_eq!(order(&records), order(&upstream.records()));
+
+    let jobs: Vec<_> = records.iter().map(|r| r.kv("job_id").unwrap()).collect();
+    assert_eq!(jobs, ["1", "1", "2", "3", "2", "3", "4", "5", "4", "5"]);
+    for record in &records {
+        if let Some(job) = record.kv("job") {
+            assert_eq!(record.kv("job_id"), Some(job));
+        }
+    }
+}
+
+/// Yields to the executor once.
+async fn yield_now() {
+    let mut yielded = false;
+    future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
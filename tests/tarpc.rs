@@ -0,0 +1,79 @@
+//! tarpc requests are served and called under loggers carrying their method
+//! and trace id.
+
+#![cfg(feature = "tarpc")]
+
+use futures_util::StreamExt;
+use slog::{info, o};
+use slog_scope_futures::{
+    tarpc::{ScopedServe, ScopedStub},
+    test_util::capture,
+};
+use tarpc::{
+    client::{self, stub::Stub, RpcError},
+    context,
+    server::{BaseChannel, Channel},
+    trace::TraceId,
+};
+
+#[tarpc::service]
+trait Accounts {
+    async fn balance(account: u32) -> u64;
+}
+
+#[derive(Clone)]
+struct AccountsServer;
+
+impl Accounts for AccountsServer {
+    async fn balance(self, _: context::Context, account: u32) -> u64 {
+        info!(slog_scope::logger(), "looking up balance"; "account" => account);
+        100
+    }
+}
+
+/// A stub logging through the current scope as each call is sent.
+struct Logging<S>(S);
+
+impl<S: Stub> Stub for Logging<S> {
+    type Req = S::Req;
+    type Resp = S::Resp;
+
+    async fn call(&self, ctx: context::Context, req: S::Req) -> Result<S::Resp, RpcError> {
+        info!(slog_scope::logger(), "sending");
+        self.0.call(ctx, req).await
+    }
+}
+
+#[tokio::test]
+async fn requests_are_scoped_on_both_sides() {
+    let (logger, records) = capture();
+    let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
+
+    let serve = ScopedServe::new(logger.new(o!("side" => "server")), AccountsServer.serve());
+    let requests = BaseChannel::with_defaults(server_transport).execute(serve);
+    tokio::spawn(requests.for_each(|response| async {
+        tokio::spawn(response);
+    }));
+
+    let channel = client::new(client::Config::default(), client_transport).spawn();
+    let stub = ScopedStub::new(logger.new(o!("side" => "client")), Logging(channel));
+    let accounts = AccountsClient::from(stub);
+
+    let mut ctx = context::current();
+    ctx.trace_context.trace_id = TraceId::from(0xabc_u128);
+    assert_eq!(accounts.balance(ctx, 7).await.unwrap(), 100);
+
+    let records = records.records();
+    assert_eq!(records.len(), 2);
+    let (client, server) = (&records[0], &records[1]);
+
+    assert_eq!(client.msg(), "sending");
+    assert_eq!(client.kv("side"), Some("client"));
+    assert_eq!(server.msg(), "looking up balance");
+    assert_eq!(server.kv("side"), Some("server"));
+    assert_eq!(server.kv("account"), Some("7"));
+    for record in &records {
+        assert_eq!(record.kv("rpc_method"), Some("Accounts.balance"));
+        assert_eq!(record.kv("trace_id"), Some("abc"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Migrations of a wrapped future between the threads polling it.
+
+use slog::info;
+use slog_scope_futures::{test_util::capture, FutureExt};
+
+#[test]
+fn migrations_and_thread_kvs_are_logged_on_a_multi_threaded_runtime() {
+    let (logger, records) = capture();
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .thread_name("worker")
+        .build()
+        .unwrap();
+
+    let tasks: Vec<_> = (0..4)
+        .map(|_| {
+            let task = async {
+                for step in 0..50 {
+                    info!(slog_scope::logger(), "step"; "step" => step);
+                    tokio::task::yield_now().await;
+                }
+            };
+            runtime.spawn(
+                task.with_logger(&logger)
+                    .tag_thread()
+                    .log_thread_migrations(),
+            )
+        })
+        .collect();
+    for task in tasks {
+        runtime.block_on(task).unwrap();
+    }
+
+    // Which migrations happen is up to the runtime, so only check that every
+    // record is tagged, and that logged migrations are between two threads.
+    let records = records.records();
+    for record in &records {
+        assert!(record.kv("thread").unwrap().starts_with("worker ("));
+        if record.msg() == "thread migration" {
+            let from = record.kv("from_thread").unwrap();
+            let to = record.kv("to_thread").unwrap();
+            assert_ne!(from, to);
+            assert_eq!(record.kv("thread"), Some(to));
+        }
+    }
+    assert_eq!(records.iter().filter(|r| r.msg() == "step").count(), 200);
+}
+
+#[test]
+fn no_migration_is_logged_on_a_single_thread() {
+    let (logger, records) = capture();
+
+    let task = async {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(task.with_logger(&logger).log_thread_migrations());
+
+    assert_eq!(records.len(), 0);
+}
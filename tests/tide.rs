@@ -0,0 +1,74 @@
+//! Handlers of a tide app log under the logger of their request.
+
+#![cfg(feature = "tide")]
+
+use slog::{info, o};
+use slog_scope_futures::{
+    test_util::capture,
+    tide::{child_logger, RequestExt, ScopeMiddleware},
+};
+use tide::http::{Method, Request, Response, Url};
+
+fn get(path: &str, request_id: Option<&str>) -> Request {
+    let url = Url::parse("http://example.com")
+        .unwrap()
+        .join(path)
+        .unwrap();
+    let mut req = Request::new(Method::Get, url);
+    if let Some(id) = request_id {
+        req.insert_header("x-request-id", id);
+    }
+    req
+}
+
+#[test]
+fn handler_logs_carry_the_request_id() {
+    let (logger, records) = capture();
+    let root = logger.new(o!("service" => "api"));
+
+    let mut app = tide::new();
+    app.with(ScopeMiddleware::new(move |req: &tide::Request<()>| {
+        child_logger(&root, req)
+    }));
+    app.at("/users/:id")
+        .get(|req: tide::Request<()>| async move {
+            info!(slog_scope::logger(), "implicit");
+            info!(req.scoped_logger(), "explicit"; "user" => req.param("id")?);
+            Ok("")
+        });
+
+    futures_executor::block_on(async {
+        let res: Response = app.respond(get("/users/7", Some("abc"))).await.unwrap();
+        assert!(res.status().is_success());
+        let res: Response = app.respond(get("/users/8", None)).await.unwrap();
+        assert!(res.status().is_success());
+    });
+
+    let records = records.records();
+    assert_eq!(records.len(), 4);
+    assert!(records[..2]
+        .iter()
+        .all(|r| r.kv("request_id") == Some("abc")));
+    assert!(records[2..]
+        .iter()
+        .all(|r| r.kv("request_id") == records[2].kv("request_id")));
+    assert_ne!(records[2].kv("request_id"), Some("abc"));
+    assert!(records.iter().all(|r| r.kv("service") == Some("api")));
+    assert_eq!(records[1].kv("user"), Some("7"));
+    assert_eq!(records[3].kv("path"), Some("/users/8"));
+}
+
+#[test]
+fn scoped_logger_falls_back_to_the_current_scope() {
+    let outer = slog::Logger::root(slog::Discard, o!("outer" => 1));
+
+    let mut app = tide::new();
+    app.at("/")
+        .get(|req: tide::Request<()>| async move { Ok(format!("{:?}", req.scoped_logger())) });
+
+    let mut res: Response = slog_scope::scope(&outer, || {
+        futures_executor::block_on(app.respond(get("/", None))).unwrap()
+    });
+    let body = futures_executor::block_on(res.body_string()).unwrap();
+    assert_eq!(body, "Logger(outer)");
+}
@@ -0,0 +1,62 @@
+//! Timeouts logged through the scope, with their timer started on first poll.
+
+#![cfg(feature = "tokio")]
+
+use std::{future, time::Duration};
+
+use slog::{o, Level};
+use slog_scope_futures::{test_util::capture, FutureExt};
+
+fn paused_runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn wrapper_can_be_built_outside_the_runtime() {
+    let (logger, records) = capture();
+    let logger = logger.new(o!("request_id" => 1));
+
+    let stuck = future::pending::<()>().with_logger(&logger).timeout_scoped(
+        Duration::from_secs(5),
+        Level::Warning,
+        "timed out",
+    );
+    let quick = future::ready(1)
+        .timeout_scoped(Duration::from_secs(5), Level::Warning, "timed out")
+        .with_logger(&logger);
+
+    let runtime = paused_runtime();
+    assert!(runtime.block_on(stuck).is_err());
+    assert_eq!(runtime.block_on(quick).unwrap(), 1);
+
+    assert_eq!(records.len(), 1);
+    assert!(records.contains_kv("request_id", "1"));
+}
+
+#[test]
+fn deadline_counts_from_the_first_poll() {
+    let (logger, records) = capture();
+
+    let runtime = paused_runtime();
+    runtime.block_on(async {
+        let stuck = future::pending::<()>().with_logger(&logger).timeout_scoped(
+            Duration::from_secs(5),
+            Level::Warning,
+            "timed out",
+        );
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let start = tokio::time::Instant::now();
+        assert!(stuck.await.is_err());
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    });
+
+    let records = records.records();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].kv("timeout_ms"), Some("5000"));
+    assert_eq!(records[0].kv("elapsed_ms"), Some("5000"));
+}
@@ -0,0 +1,82 @@
+//! Reads and writes of a wrapped connection logging with its KVs.
+
+#![cfg(feature = "tokio")]
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use slog::{info, o};
+use slog_scope_futures::{test_util::capture, SlogScope};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
+
+/// A connection logging through the active scope on every call.
+struct Conn(DuplexStream);
+
+impl AsyncRead for Conn {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        info!(slog_scope::logger(), "io"; "op" => "read");
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Conn {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        info!(slog_scope::logger(), "io"; "op" => "write");
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        info!(slog_scope::logger(), "io"; "op" => "flush");
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        info!(slog_scope::logger(), "io"; "op" => "shutdown");
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[test]
+fn reads_writes_and_shutdown_log_with_the_connection_kvs() {
+    let (logger, records) = capture();
+    let (client, mut server) = tokio::io::duplex(64);
+    let mut conn = SlogScope::new(logger.new(o!("conn_id" => 1)), Conn(client));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        conn.write_all(b"ping").await.unwrap();
+
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        server.write_all(&buf).await.unwrap();
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        conn.shutdown().await.unwrap();
+        assert_eq!(server.read(&mut buf).await.unwrap(), 0);
+    });
+
+    let records = records.records();
+    let ops: Vec<_> = records
+        .iter()
+        .filter(|r| r.kv("conn_id") == Some("1"))
+        .filter_map(|r| r.kv("op"))
+        .collect();
+    for op in ["read", "write", "shutdown"] {
+        assert!(ops.contains(&op), "no {} in {:?}", op, ops);
+    }
+    assert_eq!(ops.len(), records.len());
+}
@@ -0,0 +1,75 @@
+//! Futures entering a `tracing` span along with their slog scope.
+
+#![cfg(feature = "tracing")]
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use slog::{o, Level};
+use slog_scope_futures::{test_util::capture, FutureExt};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    Layer,
+};
+
+/// Records each event's message, prefixed with the span it happened in.
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<String>>>);
+
+impl<S> Layer<S> for Events
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        struct Message(String);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        let span = ctx.event_span(event).map_or("none", |span| span.name());
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{}: {}", span, message.0));
+    }
+}
+
+#[test]
+fn slog_and_tracing_both_see_the_poll() {
+    let (logger, records) = capture();
+    let events = Events::default();
+    let subscriber = tracing_subscriber::registry().with(events.clone());
+
+    tracing::subscriber::with_default(subscriber, || {
+        let fut = async {
+            slog::info!(slog_scope::logger(), "polled");
+            tracing::info!("polled");
+        };
+
+        let fut = fut
+            .with_logger(logger.new(o!("conn" => 1)))
+            .log_completion(Level::Info, "conn closed")
+            .with_span(tracing::info_span!("conn"));
+        futures_executor::block_on(fut);
+    });
+
+    assert_eq!(*events.0.lock().unwrap(), ["conn: polled"]);
+
+    let records = records.records();
+    let logged: Vec<_> = records.iter().map(|r| (r.msg(), r.kv("conn"))).collect();
+    assert_eq!(logged, [("polled", Some("1")), ("conn closed", Some("1"))]);
+}
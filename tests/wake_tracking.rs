@@ -0,0 +1,79 @@
+//! Wake-up tracking under wakers that poll their task inline.
+
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use futures_util::{
+    future::{self, BoxFuture},
+    task::{waker, ArcWake},
+    FutureExt as _,
+};
+use slog::Level;
+use slog_scope_futures::{test_util::capture, FutureExt};
+
+/// A task polled straight from its waker, as some executors do.
+struct InlineTask {
+    future: Mutex<Option<BoxFuture<'static, ()>>>,
+}
+
+impl InlineTask {
+    fn poll(self: &Arc<Self>) {
+        let waker = waker(Arc::clone(self));
+        let mut future = self.future.lock().unwrap();
+        if let Some(inner) = future.as_mut() {
+            if inner
+                .poll_unpin(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                *future = None;
+            }
+        }
+    }
+}
+
+impl ArcWake for InlineTask {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.poll();
+    }
+}
+
+#[test]
+fn wakers_polling_inline_do_not_deadlock() {
+    let (logger, records) = capture();
+
+    let stored = Arc::new(Mutex::new(None::<Waker>));
+    let mut polls = 0;
+    let conn = future::poll_fn({
+        let stored = Arc::clone(&stored);
+        move |cx| {
+            polls += 1;
+            if polls == 1 {
+                *stored.lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        }
+    })
+    .with_logger(logger)
+    .wake_latency_threshold(Duration::ZERO)
+    .log_wake_counts(Level::Info);
+
+    let task = Arc::new(InlineTask {
+        future: Mutex::new(Some(conn.boxed())),
+    });
+    task.poll();
+
+    let waker = stored.lock().unwrap().take().unwrap();
+    waker.wake();
+    assert!(task.future.lock().unwrap().is_none());
+
+    assert!(records.contains_msg("slow wake-up"));
+    let records = records.records();
+    let counts = records.iter().find(|r| r.msg() == "wake counts").unwrap();
+    assert_eq!(counts.kv("wakes"), Some("1"));
+    assert_eq!(counts.kv("polls"), Some("2"));
+}
@@ -0,0 +1,52 @@
+//! Run with `wasm-pack test --node -- --features wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use futures_channel::oneshot;
+use slog::o;
+use slog_scope_futures::{wasm::spawn_local_scoped, FutureExt};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn scopes_nest_on_wasm() {
+    let outer = slog::Logger::root(slog::Discard, o!("outer" => 1));
+    let inner = outer.new(o!("inner" => 1));
+
+    slog_scope::scope(&outer, || {
+        slog_scope::scope(&inner, || {
+            assert_eq!(
+                format!("{:?}", slog_scope::logger()),
+                "Logger(inner, outer)"
+            );
+        });
+        assert_eq!(format!("{:?}", slog_scope::logger()), "Logger(outer)");
+    });
+}
+
+#[wasm_bindgen_test]
+async fn scoped_futures_keep_their_logger_across_awaits() {
+    let logger = slog::Logger::root(slog::Discard, o!("conn" => 1));
+    let (tx, rx) = oneshot::channel::<()>();
+
+    wasm_bindgen_futures::spawn_local(async move { tx.send(()).unwrap() });
+    let scope = async {
+        rx.await.unwrap();
+        format!("{:?}", slog_scope::logger())
+    };
+
+    assert_eq!(scope.with_logger(logger).await, "Logger(conn)");
+}
+
+#[wasm_bindgen_test]
+async fn spawned_tasks_keep_the_spawning_scope() {
+    let request = slog::Logger::root(slog::Discard, o!("request_id" => 1));
+    let (tx, rx) = oneshot::channel();
+
+    slog_scope::scope(&request, || {
+        spawn_local_scoped(async move {
+            tx.send(format!("{:?}", slog_scope::logger())).unwrap();
+        })
+    });
+
+    assert_eq!(rx.await.unwrap(), "Logger(request_id)");
+}